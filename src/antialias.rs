@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use framebuffer::FrameBuffer;
+use text::{self, TextStyle};
+use DisplayError;
+
+/// The 8-bit coverage of a single character, positioned relative to the pen.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct CoverageGlyph<'a> {
+  /// Width of the glyph in pixels.
+  pub width: i32,
+  /// Height of the glyph in pixels.
+  pub height: i32,
+  /// Horizontal distance from the pen to the left edge of the glyph.
+  pub x_offset: i32,
+  /// Vertical distance from the top of the line to the top edge of the glyph.
+  pub y_offset: i32,
+  /// Horizontal distance the pen moves after drawing the glyph.
+  pub advance: i32,
+  /// Distance in bytes between the starts of consecutive rows of `coverage`.
+  pub stride: usize,
+  /// Coverage of each pixel, from `0` (transparent) to `255` (opaque), one byte per pixel.
+  pub coverage: &'a [u8],
+}
+
+impl<'a> CoverageGlyph<'a> {
+  /// Returns the coverage of the pixel at (`x`, `y`) of the glyph.
+  pub fn coverage(&self, x: i32, y: i32) -> u8 {
+    if x < 0 || y < 0 || x >= self.width || y >= self.height {
+      return 0;
+    }
+    self.coverage.get(y as usize * self.stride + x as usize).cloned().unwrap_or(0)
+  }
+}
+
+/// A source of anti-aliased glyphs for drawing text.
+pub trait CoverageFont {
+  /// Distance in pixels between the tops of consecutive lines of text.
+  fn line_height(&self) -> i32;
+
+  /// Returns the glyph for `character`, or `None` if the font does not include it.
+  fn glyph(&self, character: char) -> Option<CoverageGlyph<'_>>;
+}
+
+/// Location of a glyph within a `GlyphAtlas` and its metrics.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct AtlasGlyph {
+  /// Left edge of the glyph in the atlas image.
+  pub x: usize,
+  /// Top edge of the glyph in the atlas image.
+  pub y: usize,
+  /// Width of the glyph in pixels.
+  pub width: usize,
+  /// Height of the glyph in pixels.
+  pub height: usize,
+  /// Horizontal distance from the pen to the left edge of the glyph.
+  pub x_offset: i32,
+  /// Vertical distance from the top of the line to the top edge of the glyph.
+  pub y_offset: i32,
+  /// Horizontal distance the pen moves after drawing the glyph.
+  pub advance: i32,
+}
+
+/// A pre-rasterized anti-aliased font, made up of an 8-bit coverage image holding every
+/// glyph and the location of each character's glyph within it.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct GlyphAtlas {
+  width: usize,
+  height: usize,
+  coverage: Vec<u8>,
+  line_height: i32,
+  glyphs: BTreeMap<char, AtlasGlyph>,
+}
+
+impl GlyphAtlas {
+
+  /// Returns an atlas without glyphs over a `coverage` image of `width` by `height` pixels,
+  /// one byte per pixel row by row, for lines of text `line_height` pixels apart.
+  ///
+  /// # Returns
+  /// `DisplayError::BufferSizeMismatch` if `coverage` does not hold `width * height` bytes.
+  pub fn new(width: usize, height: usize, coverage: Vec<u8>, line_height: i32) -> Result<GlyphAtlas, DisplayError> {
+    if width.checked_mul(height) != Some(coverage.len()) {
+      return Err(DisplayError::BufferSizeMismatch);
+    }
+    Ok(GlyphAtlas { width, height, coverage, line_height, glyphs: BTreeMap::new() })
+  }
+
+  /// Maps `character` to `glyph`, replacing any glyph previously mapped to it.
+  ///
+  /// # Returns
+  /// `DisplayError::RegionOutOfBounds` if the glyph extends beyond the atlas image.
+  pub fn insert(&mut self, character: char, glyph: AtlasGlyph) -> Result<(), DisplayError> {
+    let within_x = glyph.x.checked_add(glyph.width).is_some_and(|right| right <= self.width);
+    let within_y = glyph.y.checked_add(glyph.height).is_some_and(|bottom| bottom <= self.height);
+    if !within_x || !within_y {
+      return Err(DisplayError::RegionOutOfBounds);
+    }
+    self.glyphs.insert(character, glyph);
+    Ok(())
+  }
+
+  /// Returns the number of characters mapped to glyphs.
+  pub fn len(&self) -> usize {
+    self.glyphs.len()
+  }
+
+  /// Returns whether no characters are mapped to glyphs.
+  pub fn is_empty(&self) -> bool {
+    self.glyphs.is_empty()
+  }
+
+}
+
+impl CoverageFont for GlyphAtlas {
+  fn line_height(&self) -> i32 {
+    self.line_height
+  }
+
+  fn glyph(&self, character: char) -> Option<CoverageGlyph<'_>> {
+    self.glyphs.get(&character).map(|glyph| {
+      let start = glyph.y * self.width + glyph.x;
+      let end = if glyph.height == 0 { start } else { start + (glyph.height - 1) * self.width + glyph.width };
+      CoverageGlyph {
+        width: glyph.width as i32,
+        height: glyph.height as i32,
+        x_offset: glyph.x_offset,
+        y_offset: glyph.y_offset,
+        advance: glyph.advance,
+        stride: self.width,
+        coverage: &self.coverage[start .. end],
+      }
+    })
+  }
+}
+
+/// Returns the glyph for `character` in `font`, falling back to `'?'` for missing characters.
+fn glyph_or_replacement<F: CoverageFont + ?Sized>(font: &F, character: char) -> Option<CoverageGlyph<'_>> {
+  font.glyph(character).or_else(|| font.glyph('?'))
+}
+
+/// Blends `value` over the pixel at (`x`, `y`) of `buffer` in proportion to `coverage`.
+pub(crate) fn blend_pixel<B: FrameBuffer + ?Sized>(buffer: &mut B, x: i32, y: i32, value: u8, coverage: u8) {
+  let background = match buffer.get_pixel(x, y) {
+    Some(background) => u32::from(background),
+    None => return,
+  };
+  let (value, coverage) = (u32::from(value.min(buffer.max_value())), u32::from(coverage));
+  let blended = (background * (255 - coverage) + value * coverage + 127) / 255;
+  buffer.set_pixel(x, y, blended as u8);
+}
+
+/// Draws `text` in the anti-aliased `font` onto `buffer` with the top of the first line at
+/// `y`, aligned to `x`. Returns the height of the lines drawn in pixels.
+pub(crate) fn draw_text<B: FrameBuffer + ?Sized, F: CoverageFont + ?Sized>(buffer: &mut B, font: &F, text: &str, x: i32, y: i32, style: &TextStyle) -> i32 {
+  let advance = |character| glyph_or_replacement(font, character).map_or(0, |glyph| glyph.advance);
+  text::layout_text(&advance, font.line_height(), text, x, y, style, |character, pen, top| {
+    if let Some(glyph) = glyph_or_replacement(font, character) {
+      for glyph_y in 0 .. glyph.height {
+        for glyph_x in 0 .. glyph.width {
+          let (pixel_x, pixel_y) = (pen + glyph.x_offset + glyph_x, top + glyph.y_offset + glyph_y);
+          let coverage = glyph.coverage(glyph_x, glyph_y);
+          if coverage != 0 && text::is_within(style.clip, pixel_x, pixel_y) {
+            blend_pixel(buffer, pixel_x, pixel_y, style.value, coverage);
+          }
+        }
+      }
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use draw::Canvas;
+  use framebuffer::{FrameBufferL1, FrameBufferL4};
+
+  /// Returns an atlas with a 2x2 glyph for `'a'` and a 1x1 glyph for `'?'`.
+  fn atlas() -> GlyphAtlas {
+    let coverage = vec![
+      255, 128, 0,
+      64,    0, 255,
+    ];
+    let mut atlas = GlyphAtlas::new(3, 2, coverage, 4).unwrap();
+    atlas.insert('a', AtlasGlyph { x: 0, y: 0, width: 2, height: 2, x_offset: 0, y_offset: 1, advance: 3 }).unwrap();
+    atlas.insert('?', AtlasGlyph { x: 2, y: 1, width: 1, height: 1, x_offset: 0, y_offset: 0, advance: 2 }).unwrap();
+    atlas
+  }
+
+  #[test]
+  fn test_atlas() {
+    let atlas = atlas();
+    assert_eq!(atlas.len(), 2);
+    let glyph = atlas.glyph('a').unwrap();
+    assert_eq!((glyph.coverage(0, 0), glyph.coverage(1, 0), glyph.coverage(0, 1), glyph.coverage(1, 1)), (255, 128, 64, 0));
+    assert_eq!(glyph.coverage(2, 0), 0);
+    assert_eq!(atlas.glyph('?').unwrap().coverage(0, 0), 255);
+    assert_eq!(atlas.glyph('b'), None);
+
+    assert_eq!(GlyphAtlas::new(3, 3, vec![0; 6], 4), Err(DisplayError::BufferSizeMismatch));
+    let mut atlas = atlas.clone();
+    let outside = AtlasGlyph { x: 2, y: 0, width: 2, height: 1, x_offset: 0, y_offset: 0, advance: 2 };
+    assert_eq!(atlas.insert('b', outside), Err(DisplayError::RegionOutOfBounds));
+  }
+
+  #[test]
+  fn test_blend() {
+    let mut buffer = FrameBufferL4::new();
+    blend_pixel(&mut buffer, 0, 0, 0xF, 255);
+    blend_pixel(&mut buffer, 1, 0, 0xF, 128);
+    blend_pixel(&mut buffer, 2, 0, 0xF, 0);
+    assert_eq!(buffer.as_frame()[0][0..2], [0xF8, 0x00]);
+
+    // Coverage blends towards the text value from the existing background.
+    buffer.fill(0xA);
+    blend_pixel(&mut buffer, 0, 0, 0x0, 128);
+    assert_eq!(buffer.get_pixel(0, 0), Some(0x5));
+
+    // One bit frame buffers are set where coverage is at least half.
+    let mut mono = FrameBufferL1::new();
+    blend_pixel(&mut mono, 0, 0, 1, 128);
+    blend_pixel(&mut mono, 1, 0, 1, 127);
+    assert_eq!(mono.as_frame()[0][0], 0b1000_0000);
+  }
+
+  #[test]
+  fn test_draw_text() {
+    let mut buffer = FrameBufferL4::new();
+    let height = buffer.draw_text_antialiased(&atlas(), "ab", 1, 0, &TextStyle::new(0xF));
+    assert_eq!(height, 4);
+    assert_eq!(buffer.get_pixel(1, 1), Some(0xF));
+    assert_eq!(buffer.get_pixel(2, 1), Some(0x8));
+    assert_eq!(buffer.get_pixel(1, 2), Some(0x4));
+    assert_eq!(buffer.get_pixel(2, 2), Some(0x0));
+
+    // Missing characters are replaced with '?', after the advance of 'a'.
+    assert_eq!(buffer.get_pixel(4, 0), Some(0xF));
+  }
+
+}
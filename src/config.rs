@@ -0,0 +1,553 @@
+use gray::{validate_gray_table, GrayTablePreset};
+use DisplayError;
+
+/// Range of the segment driver current, scaling the contrast current.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum CurrentRange {
+  /// Quarter of the full current range.
+  Quarter,
+  /// Half of the full current range.
+  Half,
+  /// Full current range.
+  Full,
+}
+
+/// Orientation of the image on the panel, relative to the mounting of the configured remap.
+/// All orientations are performed by the display, so frames are sent unchanged.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum Orientation {
+  /// The image is shown as configured.
+  Rotate0,
+  /// The image is rotated by 180 degrees, for panels mounted upside down.
+  Rotate180,
+  /// The image is mirrored horizontally.
+  FlipH,
+  /// The image is mirrored vertically.
+  FlipV,
+}
+
+/// Direction in which the common (row) outputs are scanned.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum ComRemap {
+  /// Rows are scanned from COM0 to COM79.
+  TopDown,
+  /// Rows are scanned from COM79 to COM0.
+  BottomUp,
+}
+
+/// Direction in which the display RAM address advances after each byte written.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum AddressIncrement {
+  /// The column address advances, moving to the next row at the end of the column window.
+  Horizontal,
+  /// The row address advances, moving to the next column at the end of the row window.
+  Vertical,
+}
+
+/// Arguments of the Set Re-Map command, describing how display RAM is mapped to the panel.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Remap {
+  /// Odd and even rows are driven from opposite sides of the panel.
+  pub com_split: bool,
+  /// Direction in which rows are scanned.
+  pub com_remap: ComRemap,
+  /// Direction in which the address advances after each byte written.
+  pub address_increment: AddressIncrement,
+  /// Swap the two pixels stored in each byte of display RAM.
+  pub nibble_remap: bool,
+  /// Map columns from right to left instead of left to right.
+  pub column_remap: bool,
+}
+
+impl Remap {
+  /// Returns the remap decoded from the argument byte of the Set Re-Map command.
+  pub fn from_byte(byte: u8) -> Self {
+    Remap {
+      com_split: (byte & 0x40) != 0,
+      com_remap: if (byte & 0x10) != 0 { ComRemap::BottomUp } else { ComRemap::TopDown },
+      address_increment: if (byte & 0x04) != 0 { AddressIncrement::Vertical } else { AddressIncrement::Horizontal },
+      nibble_remap: (byte & 0x02) != 0,
+      column_remap: (byte & 0x01) != 0,
+    }
+  }
+
+  /// Returns the argument byte of the Set Re-Map command for the receiver.
+  pub fn to_byte(&self) -> u8 {
+    let mut byte = 0x00;
+    if self.com_split {
+      byte |= 0x40;
+    }
+    if self.com_remap == ComRemap::BottomUp {
+      byte |= 0x10;
+    }
+    if self.address_increment == AddressIncrement::Vertical {
+      byte |= 0x04;
+    }
+    if self.nibble_remap {
+      byte |= 0x02;
+    }
+    if self.column_remap {
+      byte |= 0x01;
+    }
+    byte
+  }
+}
+
+/// Parameters sent to the display during initialization.
+/// The default configuration is tuned for the Adafruit 2.7" 128x64 module.
+/// Use `DisplayConfig::builder()` to customize it for other panels or mountings.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct DisplayConfig {
+  pub(crate) oscillator_frequency: u8,
+  pub(crate) clock_divide_ratio: u8,
+  pub(crate) multiplex_ratio: u8,
+  pub(crate) display_offset: u8,
+  pub(crate) start_line: u8,
+  pub(crate) master_config: u8,
+  pub(crate) remap: Remap,
+  pub(crate) current_range: CurrentRange,
+  pub(crate) gray_table: [u8; 8],
+  pub(crate) contrast: u8,
+  pub(crate) row_period: u8,
+  pub(crate) reset_phase_length: u8,
+  pub(crate) precharge_phase_length: u8,
+  pub(crate) precharge_compensation_level: u8,
+  pub(crate) precharge_compensation_enabled: bool,
+  pub(crate) vcomh: u8,
+  pub(crate) vsl: u8,
+  pub(crate) orientation: Orientation,
+}
+
+impl Default for DisplayConfig {
+  fn default() -> Self {
+    DisplayConfig {
+      oscillator_frequency: 0xF,
+      clock_divide_ratio: 2,
+      multiplex_ratio: 64,
+      display_offset: 76,
+      start_line: 0,
+      master_config: 0x02,
+      remap: Remap::from_byte(0x50),
+      current_range: CurrentRange::Full,
+      gray_table: GrayTablePreset::Adafruit.table(),
+      contrast: 0x7F,
+      row_period: 0x51,
+      reset_phase_length: 0x5,
+      precharge_phase_length: 0x5,
+      precharge_compensation_level: 0x02,
+      precharge_compensation_enabled: true,
+      vcomh: 0x1C,
+      // Sent by the Adafruit reference driver, which marks it as possibly wrong. The datasheet
+      // does not document it, so the emulator flags it in strict mode.
+      vsl: 0x0D | 0x02,
+      orientation: Orientation::Rotate0,
+    }
+  }
+}
+
+impl DisplayConfig {
+
+  /// Returns a builder for a configuration, starting from the default configuration.
+  pub fn builder() -> DisplayConfigBuilder {
+    DisplayConfigBuilder { config: DisplayConfig::default() }
+  }
+
+  /// Returns a builder for a configuration, starting from the receiver.
+  pub fn to_builder(&self) -> DisplayConfigBuilder {
+    DisplayConfigBuilder { config: *self }
+  }
+
+  /// Number of rows driven by the display.
+  pub fn multiplex_ratio(&self) -> u8 {
+    self.multiplex_ratio
+  }
+
+  /// Row of the panel driven by the display start line.
+  pub fn display_offset(&self) -> u8 {
+    self.display_offset
+  }
+
+  /// Row of display RAM shown at the display start line.
+  pub fn start_line(&self) -> u8 {
+    self.start_line
+  }
+
+  /// Mapping of display RAM to the panel.
+  pub fn remap(&self) -> Remap {
+    self.remap
+  }
+
+  /// Contrast current.
+  pub fn contrast(&self) -> u8 {
+    self.contrast
+  }
+
+  /// Orientation of the image on the panel.
+  pub fn orientation(&self) -> Orientation {
+    self.orientation
+  }
+
+  /// Returns the remap sent to the display, combining the configured remap and orientation.
+  ///
+  /// Mirroring horizontally reverses the column addresses, which the display performs in
+  /// units of two pixels, so the nibbles of each byte are swapped as well. Mirroring vertically
+  /// reverses the direction in which rows are scanned.
+  pub(crate) fn oriented_remap(&self) -> Remap {
+    let mut remap = self.remap;
+    if self.orientation == Orientation::FlipH || self.orientation == Orientation::Rotate180 {
+      remap.column_remap = !remap.column_remap;
+      remap.nibble_remap = !remap.nibble_remap;
+    }
+    if self.orientation == Orientation::FlipV || self.orientation == Orientation::Rotate180 {
+      remap.com_remap = match remap.com_remap {
+        ComRemap::TopDown => ComRemap::BottomUp,
+        ComRemap::BottomUp => ComRemap::TopDown,
+      };
+    }
+    remap
+  }
+
+  /// Returns the display offset sent to the display, combining the configured offset and
+  /// orientation.
+  ///
+  /// Reversing the scan direction mirrors the rows driven across all 80 rows of the display
+  /// rather than those of the panel, and reverses the direction the offset moves them in. The
+  /// offset is negated and adjusted by the rows left undriven by the multiplex ratio to keep
+  /// the image on the panel.
+  pub(crate) fn oriented_display_offset(&self) -> u8 {
+    if self.orientation == Orientation::FlipV || self.orientation == Orientation::Rotate180 {
+      let undriven = 80 - self.multiplex_ratio;
+      (2 * 80 - self.display_offset - undriven) % 80
+    } else {
+      self.display_offset
+    }
+  }
+
+  /// Returns the command sequence initializing the display with the receiver.
+  /// The display is left Off with the Draw Rectangle fill option enabled.
+  pub(crate) fn init_sequence(&self) -> Vec<u8> {
+    use commands::*;
+
+    let precharge_compensation_enable = if self.precharge_compensation_enabled { 0x28 } else { 0x08 };
+    let current_range = match self.current_range {
+      CurrentRange::Quarter => SETCURRENT_QUARTER,
+      CurrentRange::Half => SETCURRENT_HALF,
+      CurrentRange::Full => SETCURRENT_FULL,
+    };
+    vec![
+      // Turn the display off.
+      DISPLAYOFF,
+      // Set the oscillator frequency and division.
+      SETCLOCK, (self.oscillator_frequency << 4) | (self.clock_divide_ratio - 1),
+      // Set the multiplex ratio.
+      SETMULTIPLEX, self.multiplex_ratio - 1,
+      // Set the display offset.
+      SETOFFSET, self.oriented_display_offset(),
+      // Set the start line.
+      SETSTARTLINE, self.start_line,
+      // Set Master Config.
+      MASTERCONFIG, self.master_config,
+      // Set segment remap.
+      SETREMAP, self.oriented_remap().to_byte(),
+      // Set the current range.
+      current_range,
+      // Set the gray color palette.
+      SETGRAYTABLE,
+      self.gray_table[0], self.gray_table[1], self.gray_table[2], self.gray_table[3],
+      self.gray_table[4], self.gray_table[5], self.gray_table[6], self.gray_table[7],
+      // Set the contrast.
+      SETCONTRAST, self.contrast,
+      // Set the row period.
+      SETROWPERIOD, self.row_period,
+      // Set the phase length.
+      SETPHASELEN, (self.precharge_phase_length << 4) | self.reset_phase_length,
+      // Set the precharge compensation level.
+      SETPRECHARGECOMP, self.precharge_compensation_level,
+      // Enable or disable precharge compensation.
+      SETPRECHARGECOMPENABLE, precharge_compensation_enable,
+      // Set the high voltage level of the COM pin.
+      SETVCOMLEVEL, self.vcomh,
+      // Set the low voltage level of the SEG pin.
+      SETVSL, self.vsl,
+      // Set the display to non-inverted configuration.
+      NORMALDISPLAY,
+      // Turn on the Draw Rect command only, used to clear the screen.
+      GFXACCEL, GFXACCEL_FILL,
+    ]
+  }
+
+  /// Checks each parameter of the receiver against the ranges accepted by the display.
+  pub(crate) fn validate(&self) -> Result<(), DisplayError> {
+    let valid =
+      self.oscillator_frequency <= 0xF &&
+      (1 ..= 16).contains(&self.clock_divide_ratio) &&
+      (16 ..= 80).contains(&self.multiplex_ratio) &&
+      self.display_offset < 80 &&
+      self.start_line < 80 &&
+      self.master_config <= 0x03 &&
+      validate_gray_table(&self.gray_table).is_ok() &&
+      self.contrast <= 0x7F &&
+      (0x14 ..= 0x7F).contains(&self.row_period) &&
+      (1 ..= 0xF).contains(&self.reset_phase_length) &&
+      (1 ..= 0xF).contains(&self.precharge_phase_length) &&
+      self.precharge_compensation_level <= 0x07 &&
+      self.vcomh <= 0x3F &&
+      self.vsl <= 0x0F;
+    if valid {
+      Ok(())
+    } else {
+      Err(DisplayError::InvalidConfiguration)
+    }
+  }
+
+}
+
+/// Builder for a `DisplayConfig`, validating all parameters once built.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct DisplayConfigBuilder {
+  config: DisplayConfig,
+}
+
+impl DisplayConfigBuilder {
+
+  /// Sets the oscillator `frequency` from `0x0` to `0xF`, increasing with value, and the
+  /// display clock `divide_ratio` from `1` to `16`.
+  pub fn oscillator(mut self, frequency: u8, divide_ratio: u8) -> Self {
+    self.config.oscillator_frequency = frequency;
+    self.config.clock_divide_ratio = divide_ratio;
+    self
+  }
+
+  /// Sets the number of rows driven by the display, from `16` to `80`.
+  pub fn multiplex_ratio(mut self, ratio: u8) -> Self {
+    self.config.multiplex_ratio = ratio;
+    self
+  }
+
+  /// Sets the row of the panel driven by the display start line, from `0` to `79`.
+  pub fn display_offset(mut self, offset: u8) -> Self {
+    self.config.display_offset = offset;
+    self
+  }
+
+  /// Sets the row of display RAM shown at the display start line, from `0` to `79`.
+  pub fn start_line(mut self, line: u8) -> Self {
+    self.config.start_line = line;
+    self
+  }
+
+  /// Sets the master configuration byte from `0x00` to `0x03`. `0x02` selects external Vcc.
+  pub fn master_config(mut self, config: u8) -> Self {
+    self.config.master_config = config;
+    self
+  }
+
+  /// Sets the mapping of display RAM to the panel.
+  pub fn remap(mut self, remap: Remap) -> Self {
+    self.config.remap = remap;
+    self
+  }
+
+  /// Sets the range of the segment driver current.
+  pub fn current_range(mut self, range: CurrentRange) -> Self {
+    self.config.current_range = range;
+    self
+  }
+
+  /// Sets the orientation of the image on the panel, relative to the configured remap.
+  pub fn orientation(mut self, orientation: Orientation) -> Self {
+    self.config.orientation = orientation;
+    self
+  }
+
+  /// Sets the gray scale table, as described in `validate_gray_table`.
+  pub fn gray_table(mut self, table: [u8; 8]) -> Self {
+    self.config.gray_table = table;
+    self
+  }
+
+  /// Sets the contrast current from `0x00` to `0x7F`.
+  pub fn contrast(mut self, contrast: u8) -> Self {
+    self.config.contrast = contrast;
+    self
+  }
+
+  /// Sets the row period from `0x14` to `0x7F`. Lower values yield higher frame rates.
+  pub fn row_period(mut self, period: u8) -> Self {
+    self.config.row_period = period;
+    self
+  }
+
+  /// Sets the length of the reset phase (Phase 1) and precharge phase (Phase 2) in display
+  /// clocks, each from `1` to `15`.
+  pub fn phase_length(mut self, reset: u8, precharge: u8) -> Self {
+    self.config.reset_phase_length = reset;
+    self.config.precharge_phase_length = precharge;
+    self
+  }
+
+  /// Sets the precharge compensation `level` from `0` to `7`, and whether it is `enabled`.
+  pub fn precharge_compensation(mut self, level: u8, enabled: bool) -> Self {
+    self.config.precharge_compensation_level = level;
+    self.config.precharge_compensation_enabled = enabled;
+    self
+  }
+
+  /// Sets the high voltage level of the COM pins from `0x00` to `0x3F`.
+  pub fn vcomh(mut self, level: u8) -> Self {
+    self.config.vcomh = level;
+    self
+  }
+
+  /// Sets the low voltage level of the SEG pins from `0x00` to `0x0F`.
+  /// The datasheet documents `0x02` when the VSL pin is not connected, and `0x0E` when a
+  /// capacitor is connected between VSL and VSS.
+  pub fn vsl(mut self, level: u8) -> Self {
+    self.config.vsl = level;
+    self
+  }
+
+  /// Returns the configuration if every parameter is within the range accepted by the display.
+  ///
+  /// # Returns
+  /// `DisplayError::InvalidConfiguration` if any parameter is out of range.
+  pub fn build(self) -> Result<DisplayConfig, DisplayError> {
+    self.config.validate()?;
+    Ok(self.config)
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use commands;
+
+  #[test]
+  fn test_remap_round_trip() {
+    let remap = Remap::from_byte(0x50);
+    assert_eq!(remap, Remap {
+      com_split: true,
+      com_remap: ComRemap::BottomUp,
+      address_increment: AddressIncrement::Horizontal,
+      nibble_remap: false,
+      column_remap: false,
+    });
+    for byte in [0x00, 0x50, 0x57, 0x13, 0x44].iter() {
+      assert_eq!(Remap::from_byte(*byte).to_byte(), *byte);
+    }
+  }
+
+  #[test]
+  fn test_default_init_sequence() {
+    use commands::*;
+
+    // The default configuration matches the sequence tuned for the Adafruit module.
+    let expected: &[u8] = &[
+      DISPLAYOFF,
+      SETCLOCK, 0xF1,
+      SETMULTIPLEX, 0x3F,
+      SETOFFSET, 0x4C,
+      SETSTARTLINE, 0x00,
+      MASTERCONFIG, 0x02,
+      SETREMAP, 0x50,
+      SETCURRENT_FULL,
+      SETGRAYTABLE, 0x01, 0x11, 0x22, 0x32, 0x43, 0x54, 0x65, 0x76,
+      SETCONTRAST, 0x7F,
+      SETROWPERIOD, 0x51,
+      SETPHASELEN, 0x55,
+      SETPRECHARGECOMP, 0x02,
+      SETPRECHARGECOMPENABLE, 0x28,
+      SETVCOMLEVEL, 0x1C,
+      SETVSL, 0x0F,
+      NORMALDISPLAY,
+      GFXACCEL, 0x01,
+    ];
+    assert_eq!(DisplayConfig::default().init_sequence(), expected);
+    assert_eq!(DisplayConfig::builder().build(), Ok(DisplayConfig::default()));
+  }
+
+  #[test]
+  fn test_builder() {
+    let config = DisplayConfig::builder()
+      .oscillator(0x9, 1)
+      .multiplex_ratio(80)
+      .display_offset(0)
+      .phase_length(0x2, 0x7)
+      .precharge_compensation(0x03, false)
+      .current_range(CurrentRange::Half)
+      .build()
+      .unwrap();
+    let sequence = config.init_sequence();
+    assert_eq!(&sequence[1..9], [
+      commands::SETCLOCK, 0x90,
+      commands::SETMULTIPLEX, 0x4F,
+      commands::SETOFFSET, 0x00,
+      commands::SETSTARTLINE, 0x00,
+    ]);
+    assert_eq!(sequence[13], commands::SETCURRENT_HALF);
+    assert_eq!(&sequence[27..33], [
+      commands::SETPHASELEN, 0x72,
+      commands::SETPRECHARGECOMP, 0x03,
+      commands::SETPRECHARGECOMPENABLE, 0x08,
+    ]);
+  }
+
+  #[test]
+  fn test_orientation() {
+    let remap_and_offset = |orientation| {
+      let config = DisplayConfig::builder().orientation(orientation).build().unwrap();
+      (config.oriented_remap().to_byte(), config.oriented_display_offset())
+    };
+    assert_eq!(remap_and_offset(Orientation::Rotate0), (0x50, 76));
+    assert_eq!(remap_and_offset(Orientation::FlipH), (0x53, 76));
+    assert_eq!(remap_and_offset(Orientation::FlipV), (0x40, 68));
+    assert_eq!(remap_and_offset(Orientation::Rotate180), (0x43, 68));
+
+    // Flipping a configuration scanning top-down restores the offset scanning bottom-up.
+    let config = DisplayConfig::builder()
+      .remap(Remap::from_byte(0x40))
+      .display_offset(68)
+      .orientation(Orientation::FlipV)
+      .build()
+      .unwrap();
+    assert_eq!((config.oriented_remap().to_byte(), config.oriented_display_offset()), (0x50, 76));
+
+    // Panels driving all 80 rows need no adjustment.
+    let config = DisplayConfig::builder()
+      .multiplex_ratio(80)
+      .display_offset(0)
+      .orientation(Orientation::Rotate180)
+      .build()
+      .unwrap();
+    assert_eq!(config.oriented_display_offset(), 0);
+  }
+
+  #[test]
+  fn test_builder_validation() {
+    let invalid = [
+      DisplayConfig::builder().oscillator(0x10, 1),
+      DisplayConfig::builder().oscillator(0x0, 0),
+      DisplayConfig::builder().oscillator(0x0, 17),
+      DisplayConfig::builder().multiplex_ratio(15),
+      DisplayConfig::builder().multiplex_ratio(81),
+      DisplayConfig::builder().display_offset(80),
+      DisplayConfig::builder().start_line(80),
+      DisplayConfig::builder().master_config(0x04),
+      DisplayConfig::builder().gray_table([0x08, 0, 0, 0, 0, 0, 0, 0]),
+      DisplayConfig::builder().gray_table([0, 0, 0, 0, 0, 0, 0, 0x80]),
+      DisplayConfig::builder().contrast(0x80),
+      DisplayConfig::builder().row_period(0x13),
+      DisplayConfig::builder().row_period(0x80),
+      DisplayConfig::builder().phase_length(0x0, 0x5),
+      DisplayConfig::builder().phase_length(0x5, 0x10),
+      DisplayConfig::builder().precharge_compensation(0x08, true),
+      DisplayConfig::builder().vcomh(0x40),
+      DisplayConfig::builder().vsl(0x10),
+    ];
+    for builder in invalid.iter() {
+      assert_eq!(builder.build(), Err(DisplayError::InvalidConfiguration));
+    }
+  }
+
+}
@@ -0,0 +1,390 @@
+use std::fmt;
+
+use commands::*;
+use config::{ComRemap, CurrentRange, AddressIncrement, Remap};
+use emulator::{argument_count, ViewMode};
+use {DisplayMode, ScrollInterval};
+
+/// Number of bytes of display data shown by the `Display` implementation of `Command`.
+const DATA_PREVIEW_LENGTH: usize = 16;
+
+/// A command or run of display data decoded from the bytes sent to an SSD1325.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Command {
+  /// The control channel was placed in `DisplayMode::Reset`.
+  Reset,
+  /// Display data, written to display RAM at the current address.
+  Data {
+    bytes: Vec<u8>,
+  },
+  /// Set Column Address.
+  SetColumnAddress {
+    start: u8,
+    end: u8,
+  },
+  /// Set Row Address.
+  SetRowAddress {
+    start: u8,
+    end: u8,
+  },
+  /// Set Contrast Current.
+  SetContrast {
+    contrast: u8,
+  },
+  /// Set Current Range.
+  SetCurrentRange {
+    range: CurrentRange,
+  },
+  /// Set Re-Map.
+  SetRemap(Remap),
+  /// Set Display Start Line.
+  SetStartLine {
+    line: u8,
+  },
+  /// Set Display Offset.
+  SetDisplayOffset {
+    offset: u8,
+  },
+  /// Set Display Mode.
+  SetDisplayMode {
+    mode: ViewMode,
+  },
+  /// Set Multiplex Ratio, as the number of rows scanned.
+  SetMultiplexRatio {
+    ratio: u8,
+  },
+  /// Set Master Configuration.
+  SetMasterConfig {
+    config: u8,
+  },
+  /// Set Display Off.
+  DisplayOff,
+  /// Set Display On.
+  DisplayOn,
+  /// Set Pre-charge Compensation Enable.
+  SetPrechargeCompensationEnable {
+    enabled: bool,
+  },
+  /// Set Phase Length, in display clocks.
+  SetPhaseLength {
+    reset: u8,
+    precharge: u8,
+  },
+  /// Set Row Period.
+  SetRowPeriod {
+    period: u8,
+  },
+  /// Set Display Clock Divide Ratio and oscillator frequency.
+  SetClock {
+    oscillator_frequency: u8,
+    divide_ratio: u8,
+  },
+  /// Set Pre-charge Compensation Level.
+  SetPrechargeCompensationLevel {
+    level: u8,
+  },
+  /// Set Gray Scale Table.
+  SetGrayTable {
+    table: [u8; 8],
+  },
+  /// Set VCOMH Voltage.
+  SetVcomhLevel {
+    level: u8,
+  },
+  /// Set Segment Low Voltage.
+  SetVsl {
+    level: u8,
+  },
+  /// Graphic Acceleration Command Options.
+  GraphicAcceleration {
+    fill: bool,
+    wrap: bool,
+    reverse: bool,
+  },
+  /// Draw Rectangle, in columns of two pixels and rows of display RAM.
+  DrawRect {
+    start_column: u8,
+    start_row: u8,
+    end_column: u8,
+    end_row: u8,
+    pattern: u8,
+  },
+  /// Copy, in columns of two pixels and rows of display RAM.
+  Copy {
+    start_column: u8,
+    start_row: u8,
+    end_column: u8,
+    end_row: u8,
+    new_column: u8,
+    new_row: u8,
+  },
+  /// Horizontal Scroll Setup.
+  SetupHorizontalScroll {
+    columns_per_step: u8,
+    start_row: u8,
+    rows: u8,
+    interval: ScrollInterval,
+  },
+  /// Deactivate Scrolling.
+  DeactivateScroll,
+  /// Activate Scrolling.
+  ActivateScroll,
+  /// No Operation.
+  Nop,
+  /// A command byte which is not part of the command set of the display.
+  Unknown {
+    command: u8,
+  },
+  /// A command followed by fewer argument bytes than it takes before the control channel left
+  /// command mode or the stream ended.
+  Incomplete {
+    bytes: Vec<u8>,
+  },
+}
+
+impl Command {
+  /// Returns the command decoded from a command byte followed by all of its arguments.
+  fn decode(bytes: &[u8]) -> Command {
+    let arguments = &bytes[1 ..];
+    match bytes[0] {
+      SETCOLADDR => Command::SetColumnAddress { start: arguments[0], end: arguments[1] },
+      SETROWADDR => Command::SetRowAddress { start: arguments[0], end: arguments[1] },
+      SETCONTRAST => Command::SetContrast { contrast: arguments[0] },
+      SETCURRENT_QUARTER => Command::SetCurrentRange { range: CurrentRange::Quarter },
+      SETCURRENT_HALF => Command::SetCurrentRange { range: CurrentRange::Half },
+      SETCURRENT_FULL => Command::SetCurrentRange { range: CurrentRange::Full },
+      SETREMAP => Command::SetRemap(Remap::from_byte(arguments[0])),
+      SETSTARTLINE => Command::SetStartLine { line: arguments[0] },
+      SETOFFSET => Command::SetDisplayOffset { offset: arguments[0] },
+      NORMALDISPLAY => Command::SetDisplayMode { mode: ViewMode::Normal },
+      ALLONDISPLAY => Command::SetDisplayMode { mode: ViewMode::AllOn },
+      ALLOFFDISPLAY => Command::SetDisplayMode { mode: ViewMode::AllOff },
+      INVERTDISPLAY => Command::SetDisplayMode { mode: ViewMode::Inverse },
+      SETMULTIPLEX => Command::SetMultiplexRatio { ratio: (arguments[0] & 0x7F) + 1 },
+      MASTERCONFIG => Command::SetMasterConfig { config: arguments[0] },
+      DISPLAYOFF => Command::DisplayOff,
+      DISPLAYON => Command::DisplayOn,
+      SETPRECHARGECOMPENABLE => Command::SetPrechargeCompensationEnable { enabled: (arguments[0] & 0x20) != 0 },
+      SETPHASELEN => Command::SetPhaseLength { reset: arguments[0] & 0x0F, precharge: arguments[0] >> 4 },
+      SETROWPERIOD => Command::SetRowPeriod { period: arguments[0] },
+      SETCLOCK => Command::SetClock { oscillator_frequency: arguments[0] >> 4, divide_ratio: (arguments[0] & 0x0F) + 1 },
+      SETPRECHARGECOMP => Command::SetPrechargeCompensationLevel { level: arguments[0] },
+      SETGRAYTABLE => {
+        let mut table = [0u8; 8];
+        table.copy_from_slice(arguments);
+        Command::SetGrayTable { table }
+      }
+      SETVCOMLEVEL => Command::SetVcomhLevel { level: arguments[0] },
+      SETVSL => Command::SetVsl { level: arguments[0] },
+      GFXACCEL => Command::GraphicAcceleration {
+        fill: (arguments[0] & GFXACCEL_FILL) != 0,
+        wrap: (arguments[0] & GFXACCEL_WRAP) != 0,
+        reverse: (arguments[0] & GFXACCEL_REVERSE) != 0,
+      },
+      DRAWRECT => Command::DrawRect {
+        start_column: arguments[0],
+        start_row: arguments[1],
+        end_column: arguments[2],
+        end_row: arguments[3],
+        pattern: arguments[4],
+      },
+      COPY => Command::Copy {
+        start_column: arguments[0],
+        start_row: arguments[1],
+        end_column: arguments[2],
+        end_row: arguments[3],
+        new_column: arguments[4],
+        new_row: arguments[5],
+      },
+      SETUPHSCROLL => Command::SetupHorizontalScroll {
+        columns_per_step: arguments[0],
+        start_row: arguments[1],
+        rows: arguments[2],
+        interval: match arguments[3] & 0x03 {
+          0x00 => ScrollInterval::Frames6,
+          0x01 => ScrollInterval::Frames10,
+          0x02 => ScrollInterval::Frames100,
+          _ => ScrollInterval::Frames200,
+        },
+      },
+      DEACTIVATESCROLL => Command::DeactivateScroll,
+      ACTIVATESCROLL => Command::ActivateScroll,
+      NOP => Command::Nop,
+      command => Command::Unknown { command },
+    }
+  }
+}
+
+/// Writes `bytes` as space-separated hexadecimal, eliding all but the first few.
+fn write_bytes(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+  for (index, byte) in bytes.iter().take(DATA_PREVIEW_LENGTH).enumerate() {
+    write!(f, "{}{:02X}", if index == 0 { "" } else { " " }, byte)?;
+  }
+  if bytes.len() > DATA_PREVIEW_LENGTH {
+    write!(f, " ...")?;
+  }
+  Ok(())
+}
+
+impl fmt::Display for Command {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Command::Reset => write!(f, "Reset"),
+      Command::Data { ref bytes } => {
+        write!(f, "Data ({} bytes): ", bytes.len())?;
+        write_bytes(f, bytes)
+      }
+      Command::SetColumnAddress { start, end } => write!(f, "Set Column Address {} to {}", start, end),
+      Command::SetRowAddress { start, end } => write!(f, "Set Row Address {} to {}", start, end),
+      Command::SetContrast { contrast } => write!(f, "Set Contrast Current {:#04X}", contrast),
+      Command::SetCurrentRange { range } => write!(f, "Set Current Range {:?}", range),
+      Command::SetRemap(remap) => {
+        write!(f, "Set Re-Map {:#04X}: rows {}, {} increment",
+          remap.to_byte(),
+          if remap.com_remap == ComRemap::BottomUp { "bottom-up" } else { "top-down" },
+          if remap.address_increment == AddressIncrement::Vertical { "vertical" } else { "horizontal" })?;
+        if remap.com_split {
+          write!(f, ", COM split")?;
+        }
+        if remap.nibble_remap {
+          write!(f, ", nibble remap")?;
+        }
+        if remap.column_remap {
+          write!(f, ", column remap")?;
+        }
+        Ok(())
+      }
+      Command::SetStartLine { line } => write!(f, "Set Display Start Line {}", line),
+      Command::SetDisplayOffset { offset } => write!(f, "Set Display Offset {}", offset),
+      Command::SetDisplayMode { mode } => write!(f, "Set Display Mode {:?}", mode),
+      Command::SetMultiplexRatio { ratio } => write!(f, "Set Multiplex Ratio {} rows", ratio),
+      Command::SetMasterConfig { config } => write!(f, "Set Master Configuration {:#04X}", config),
+      Command::DisplayOff => write!(f, "Set Display Off"),
+      Command::DisplayOn => write!(f, "Set Display On"),
+      Command::SetPrechargeCompensationEnable { enabled } =>
+        write!(f, "Set Pre-charge Compensation {}", if enabled { "Enabled" } else { "Disabled" }),
+      Command::SetPhaseLength { reset, precharge } =>
+        write!(f, "Set Phase Length reset {} DCLKs, pre-charge {} DCLKs", reset, precharge),
+      Command::SetRowPeriod { period } => write!(f, "Set Row Period {:#04X}", period),
+      Command::SetClock { oscillator_frequency, divide_ratio } =>
+        write!(f, "Set Display Clock oscillator frequency {}, divide ratio {}", oscillator_frequency, divide_ratio),
+      Command::SetPrechargeCompensationLevel { level } => write!(f, "Set Pre-charge Compensation Level {:#04X}", level),
+      Command::SetGrayTable { ref table } => {
+        write!(f, "Set Gray Scale Table ")?;
+        write_bytes(f, table)
+      }
+      Command::SetVcomhLevel { level } => write!(f, "Set VCOMH Voltage {:#04X}", level),
+      Command::SetVsl { level } => write!(f, "Set Segment Low Voltage {:#04X}", level),
+      Command::GraphicAcceleration { fill, wrap, reverse } =>
+        write!(f, "Graphic Acceleration fill {}, wrap {}, reverse {}", fill, wrap, reverse),
+      Command::DrawRect { start_column, start_row, end_column, end_row, pattern } =>
+        write!(f, "Draw Rectangle ({}, {}) to ({}, {}) with {:#04X}", start_column, start_row, end_column, end_row, pattern),
+      Command::Copy { start_column, start_row, end_column, end_row, new_column, new_row } =>
+        write!(f, "Copy ({}, {}) to ({}, {}) onto ({}, {})", start_column, start_row, end_column, end_row, new_column, new_row),
+      Command::SetupHorizontalScroll { columns_per_step, start_row, rows, interval } =>
+        write!(f, "Horizontal Scroll Setup {} columns per step, {} rows from {}, every {:?}",
+          columns_per_step, rows, start_row, interval),
+      Command::DeactivateScroll => write!(f, "Deactivate Scrolling"),
+      Command::ActivateScroll => write!(f, "Activate Scrolling"),
+      Command::Nop => write!(f, "No Operation"),
+      Command::Unknown { command } => write!(f, "Unknown Command {:#04X}", command),
+      Command::Incomplete { ref bytes } => {
+        write!(f, "Incomplete Command ")?;
+        write_bytes(f, bytes)
+      }
+    }
+  }
+}
+
+/// Decodes a captured `stream` of bytes sent to the display, each run of bytes paired with the
+/// mode of the control channel it was sent in, into the commands and display data they form.
+///
+/// As on the display, the arguments of a command may follow it in a later run of bytes sent in
+/// command mode. Consecutive runs of display data are merged, bytes sent while idle are
+/// ignored, and each run sent in reset mode is decoded as `Command::Reset`.
+pub fn disassemble<'a, I: IntoIterator<Item = (DisplayMode, &'a [u8])>>(stream: I) -> Vec<Command> {
+  let mut commands = Vec::new();
+  let mut pending = Vec::new();
+  for (mode, bytes) in stream {
+    if mode != DisplayMode::Command && !pending.is_empty() {
+      commands.push(Command::Incomplete { bytes: ::std::mem::take(&mut pending) });
+    }
+    match mode {
+      DisplayMode::Command => {
+        for &byte in bytes {
+          pending.push(byte);
+          if pending.len() > argument_count(pending[0]) {
+            commands.push(Command::decode(&pending));
+            pending.clear();
+          }
+        }
+      }
+      DisplayMode::Data => {
+        if let Some(&mut Command::Data { bytes: ref mut data }) = commands.last_mut() {
+          data.extend_from_slice(bytes);
+          continue;
+        }
+        commands.push(Command::Data { bytes: bytes.to_vec() });
+      }
+      DisplayMode::Reset => commands.push(Command::Reset),
+      DisplayMode::Idle => {}
+    }
+  }
+  if !pending.is_empty() {
+    commands.push(Command::Incomplete { bytes: pending });
+  }
+  commands
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use config::DisplayConfig;
+
+  #[test]
+  fn test_disassemble_init_sequence() {
+    let sequence = DisplayConfig::default().init_sequence();
+    let commands = disassemble(vec![(DisplayMode::Reset, &[][..]), (DisplayMode::Command, &sequence[..])]);
+    assert_eq!(commands[0], Command::Reset);
+    assert_eq!(commands[1], Command::DisplayOff);
+    assert!(commands.contains(&Command::SetMultiplexRatio { ratio: 64 }));
+    assert!(commands.contains(&Command::SetRemap(Remap::from_byte(0x50))));
+    assert!(commands.contains(&Command::SetVsl { level: 0x0F }));
+    assert!(!commands.iter().any(|command| matches!(*command, Command::Unknown { .. } | Command::Incomplete { .. })));
+  }
+
+  #[test]
+  fn test_disassemble_stream() {
+    let stream: Vec<(DisplayMode, &[u8])> = vec![
+      (DisplayMode::Command, &[SETCOLADDR, 0]),
+      (DisplayMode::Command, &[63, GFXACCEL, 0x11]),
+      (DisplayMode::Data, &[0x12, 0x34]),
+      (DisplayMode::Idle, &[0xFF]),
+      (DisplayMode::Data, &[0x56]),
+      (DisplayMode::Command, &[0x00, SETCONTRAST]),
+      (DisplayMode::Data, &[0x78]),
+      (DisplayMode::Command, &[DRAWRECT, 0, 0]),
+    ];
+    assert_eq!(disassemble(stream), vec![
+      Command::SetColumnAddress { start: 0, end: 63 },
+      Command::GraphicAcceleration { fill: true, wrap: false, reverse: true },
+      Command::Data { bytes: vec![0x12, 0x34, 0x56] },
+      Command::Unknown { command: 0x00 },
+      Command::Incomplete { bytes: vec![SETCONTRAST] },
+      Command::Data { bytes: vec![0x78] },
+      Command::Incomplete { bytes: vec![DRAWRECT, 0, 0] },
+    ]);
+  }
+
+  #[test]
+  fn test_display() {
+    assert_eq!(Command::SetRemap(Remap::from_byte(0x53)).to_string(),
+      "Set Re-Map 0x53: rows bottom-up, horizontal increment, COM split, nibble remap, column remap");
+    assert_eq!(Command::SetContrast { contrast: 0x7F }.to_string(), "Set Contrast Current 0x7F");
+    assert_eq!(Command::Data { bytes: vec![0xAB; 20] }.to_string(),
+      "Data (20 bytes): AB AB AB AB AB AB AB AB AB AB AB AB AB AB AB AB ...");
+    let scroll = Command::SetupHorizontalScroll { columns_per_step: 1, start_row: 48, rows: 16, interval: ScrollInterval::Frames6 };
+    assert_eq!(scroll.to_string(), "Horizontal Scroll Setup 1 columns per step, 16 rows from 48, every Frames6");
+  }
+
+}
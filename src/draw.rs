@@ -0,0 +1,709 @@
+use std::ops::RangeInclusive;
+
+use antialias::{self, CoverageFont};
+use framebuffer::FrameBuffer;
+use text::{self, Font, TextStyle};
+use {HEIGHT, WIDTH};
+
+/// An 8x8 1-bit pattern, repeated across the image when filling a shape.
+/// Each byte is a row with the most significant bit on the left; pixels whose bit is set are
+/// painted, while the others are left unchanged. The pattern is aligned to the image, so
+/// adjacent shapes filled with the same pattern line up.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Pattern(pub [u8; 8]);
+
+impl Pattern {
+  /// Paints every pixel.
+  pub const SOLID: Pattern = Pattern([0xFF; 8]);
+  /// Paints every other pixel, alternating between rows.
+  pub const CHECKERBOARD: Pattern = Pattern([0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55]);
+  /// Paints every other row.
+  pub const HORIZONTAL_LINES: Pattern = Pattern([0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00]);
+  /// Paints every other column.
+  pub const VERTICAL_LINES: Pattern = Pattern([0xAA; 8]);
+  /// Paints one pixel in four, for a light shade.
+  pub const DOTS: Pattern = Pattern([0x88, 0x00, 0x22, 0x00, 0x88, 0x00, 0x22, 0x00]);
+  /// Paints diagonal lines running from the bottom-left to the top-right.
+  pub const DIAGONAL: Pattern = Pattern([0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80]);
+
+  /// Returns whether the pattern paints the pixel at (`x`, `y`).
+  pub fn contains(&self, x: i32, y: i32) -> bool {
+    let row = self.0[(y & 7) as usize];
+    (row & (0x80 >> (x & 7))) != 0
+  }
+}
+
+/// Describes how the interior of a shape is painted.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Fill {
+  /// Value painted by the pattern.
+  pub value: u8,
+  /// Pattern selecting the pixels which are painted.
+  pub pattern: Pattern,
+}
+
+impl Fill {
+  /// Returns a fill painting every pixel with `value`.
+  pub fn solid(value: u8) -> Self {
+    Fill { value, pattern: Pattern::SOLID }
+  }
+}
+
+/// Describes how a shape is drawn: its outline is painted with `stroke` and its interior with
+/// `fill`. Either may be omitted.
+#[derive(Copy,Clone,Debug,Default,Eq,PartialEq)]
+pub struct Style {
+  /// Value of the one pixel wide outline of the shape.
+  pub stroke: Option<u8>,
+  /// Fill of the interior of the shape, painted before the outline.
+  pub fill: Option<Fill>,
+}
+
+impl Style {
+  /// Returns a style drawing only the outline of a shape with `value`.
+  pub fn stroke(value: u8) -> Self {
+    Style { stroke: Some(value), fill: None }
+  }
+
+  /// Returns a style filling a shape with `fill`, without an outline.
+  pub fn fill(fill: Fill) -> Self {
+    Style { stroke: None, fill: Some(fill) }
+  }
+
+  /// Returns a style filling a shape with `fill` and drawing its outline with `value`.
+  pub fn stroke_and_fill(value: u8, fill: Fill) -> Self {
+    Style { stroke: Some(value), fill: Some(fill) }
+  }
+}
+
+/// Drawing primitives, available on every `FrameBuffer`.
+///
+/// Shapes may extend beyond the edges of the image, and are clipped to it. Corners and
+/// points are inclusive. Angles are in degrees, measured clockwise from the positive X axis
+/// as the Y axis points down.
+pub trait Canvas: FrameBuffer {
+
+  /// Draws a one pixel wide line from (`x0`, `y0`) to (`x1`, `y1`) with `value`.
+  fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, value: u8) {
+    let (x0, y0, x1, y1) = match clip_line(x0, y0, x1, y1) {
+      Some(line) => line,
+      None => return,
+    };
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    loop {
+      self.set_pixel(x, y, value);
+      if x == x1 && y == y1 {
+        break;
+      }
+      let doubled = 2 * error;
+      if doubled >= dy {
+        error += dy;
+        x += step_x;
+      }
+      if doubled <= dx {
+        error += dx;
+        y += step_y;
+      }
+    }
+  }
+
+  /// Draws the rectangle with corners (`x0`, `y0`) and (`x1`, `y1`) in `style`.
+  fn draw_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, style: Style) {
+    let (x0, x1) = (x0.min(x1), x0.max(x1));
+    let (y0, y1) = (y0.min(y1), y0.max(y1));
+    if let Some(fill) = style.fill {
+      for y in visible_span(i64::from(y0), i64::from(y1), HEIGHT) {
+        fill_span(self, i64::from(x0), i64::from(x1), y, fill);
+      }
+    }
+    if let Some(value) = style.stroke {
+      self.draw_line(x0, y0, x1, y0, value);
+      self.draw_line(x0, y1, x1, y1, value);
+      self.draw_line(x0, y0, x0, y1, value);
+      self.draw_line(x1, y0, x1, y1, value);
+    }
+  }
+
+  /// Draws the rectangle with corners (`x0`, `y0`) and (`x1`, `y1`), with its corners rounded
+  /// to `radius`, in `style`. The radius is limited to half of the shorter side.
+  fn draw_rounded_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, radius: i32, style: Style) {
+    let (x0, x1) = (i64::from(x0.min(x1)), i64::from(x0.max(x1)));
+    let (y0, y1) = (i64::from(y0.min(y1)), i64::from(y0.max(y1)));
+    let radius = i64::from(radius).max(0).min((x1 - x0) / 2).min((y1 - y0) / 2);
+    // Centers of the corners, which are within the rectangle.
+    let (left, right, top, bottom) = (x0 + radius, x1 - radius, y0 + radius, y1 - radius);
+
+    if let Some(fill) = style.fill {
+      for y in visible_span(y0, y1, HEIGHT) {
+        let corner_dy = (top - y).max(y - bottom).max(0);
+        let inset = if corner_dy > 0 { radius - circle_half_width(radius, corner_dy) } else { 0 };
+        fill_span(self, x0 + inset, x1 - inset, y, fill);
+      }
+    }
+
+    if let Some(value) = style.stroke {
+      let (x0, x1, y0, y1) = (x0 as i32, x1 as i32, y0 as i32, y1 as i32);
+      let (left, right, top, bottom) = (left as i32, right as i32, top as i32, bottom as i32);
+      self.draw_line(left, y0, right, y0, value);
+      self.draw_line(left, y1, right, y1, value);
+      self.draw_line(x0, top, x0, bottom, value);
+      self.draw_line(x1, top, x1, bottom, value);
+      let (left, right, top, bottom) = (i64::from(left), i64::from(right), i64::from(top), i64::from(bottom));
+      for (dx, dy) in circle_octant(radius, &[(left, top), (right, bottom)]) {
+        for &(x, y) in [(dx, dy), (dy, dx)].iter() {
+          plot(self, right + x, bottom + y, value);
+          plot(self, left - x, bottom + y, value);
+          plot(self, right + x, top - y, value);
+          plot(self, left - x, top - y, value);
+        }
+      }
+    }
+  }
+
+  /// Draws the circle centered on (`cx`, `cy`) with `radius` in `style`.
+  fn draw_circle(&mut self, cx: i32, cy: i32, radius: i32, style: Style) {
+    let (cx, cy, radius) = (i64::from(cx), i64::from(cy), i64::from(radius.max(0)));
+    if let Some(fill) = style.fill {
+      for y in visible_span(cy - radius, cy + radius, HEIGHT) {
+        let half_width = circle_half_width(radius, (y - cy).abs());
+        fill_span(self, cx - half_width, cx + half_width, y, fill);
+      }
+    }
+    if let Some(value) = style.stroke {
+      for (dx, dy) in circle_octant(radius, &[(cx, cy)]) {
+        for &(x, y) in [(dx, dy), (dy, dx)].iter() {
+          plot(self, cx + x, cy + y, value);
+          plot(self, cx - x, cy + y, value);
+          plot(self, cx + x, cy - y, value);
+          plot(self, cx - x, cy - y, value);
+        }
+      }
+    }
+  }
+
+  /// Draws the ellipse centered on (`cx`, `cy`) with horizontal radius `rx` and vertical radius
+  /// `ry` in `style`.
+  fn draw_ellipse(&mut self, cx: i32, cy: i32, rx: i32, ry: i32, style: Style) {
+    let (cx, cy) = (i64::from(cx), i64::from(cy));
+    let (rx, ry) = (i64::from(rx.max(0)), i64::from(ry.max(0)));
+    if let Some(fill) = style.fill {
+      for y in visible_span(cy - ry, cy + ry, HEIGHT) {
+        let half_width = ellipse_half_width(rx, ry, y - cy);
+        fill_span(self, cx - half_width, cx + half_width, y, fill);
+      }
+    }
+    if let Some(value) = style.stroke {
+      // The outline is traced across each row and down each column, so that it has no gaps
+      // where it is steep or shallow.
+      for y in visible_span(cy - ry, cy + ry, HEIGHT) {
+        let half_width = ellipse_half_width(rx, ry, y - cy);
+        plot(self, cx - half_width, y, value);
+        plot(self, cx + half_width, y, value);
+      }
+      for x in visible_span(cx - rx, cx + rx, WIDTH) {
+        let half_height = ellipse_half_width(ry, rx, x - cx);
+        plot(self, x, cy - half_height, value);
+        plot(self, x, cy + half_height, value);
+      }
+    }
+  }
+
+  /// Draws the arc of the circle centered on (`cx`, `cy`) with `radius`, from `start_angle`
+  /// clockwise to `end_angle`, in `style`. Filling paints the sector enclosed by the arc.
+  fn draw_arc(&mut self, cx: i32, cy: i32, radius: i32, start_angle: i32, end_angle: i32, style: Style) {
+    let (cx, cy, radius) = (i64::from(cx), i64::from(cy), i64::from(radius.max(0)));
+    let (start, end) = (i64::from(start_angle), i64::from(end_angle));
+    let sweep = if end - start >= 360 { 360 } else { (end - start).rem_euclid(360) };
+    let start = start.rem_euclid(360);
+    let within = |dx: i64, dy: i64| {
+      if sweep == 360 || (dx == 0 && dy == 0) {
+        return true;
+      }
+      let angle = (dy as f64).atan2(dx as f64).to_degrees().round() as i64;
+      (angle - start).rem_euclid(360) <= sweep
+    };
+
+    if let Some(fill) = style.fill {
+      for y in visible_span(cy - radius, cy + radius, HEIGHT) {
+        let half_width = circle_half_width(radius, (y - cy).abs());
+        for x in visible_span(cx - half_width, cx + half_width, WIDTH) {
+          if within(x - cx, y - cy) {
+            fill_pixel(self, x as i32, y as i32, fill);
+          }
+        }
+      }
+    }
+    if let Some(value) = style.stroke {
+      for (dx, dy) in circle_octant(radius, &[(cx, cy)]) {
+        for &(x, y) in [(dx, dy), (dy, dx)].iter() {
+          for &(px, py) in [(x, y), (-x, y), (x, -y), (-x, -y)].iter() {
+            if within(px, py) {
+              plot(self, cx + px, cy + py, value);
+            }
+          }
+        }
+      }
+    }
+  }
+
+  /// Draws the closed polygon through `points` in `style`. The interior is determined by the
+  /// even-odd rule, so self-intersecting polygons have holes.
+  fn draw_polygon(&mut self, points: &[(i32, i32)], style: Style) {
+    if points.is_empty() {
+      return;
+    }
+
+    if let Some(fill) = style.fill {
+      let min_y = points.iter().map(|point| point.1).min().unwrap_or(0).max(0);
+      let max_y = points.iter().map(|point| point.1).max().unwrap_or(0).min(HEIGHT as i32 - 1);
+      let mut crossings = Vec::with_capacity(points.len());
+      for y in min_y ..= max_y {
+        // Sample each row at its center, so that vertices are never on the scan line.
+        let scan_y = f64::from(y) + 0.5;
+        crossings.clear();
+        for (index, &(ax, ay)) in points.iter().enumerate() {
+          let (bx, by) = points[(index + 1) % points.len()];
+          let (low, high) = (f64::from(ay.min(by)), f64::from(ay.max(by)));
+          if scan_y >= low && scan_y < high {
+            let t = (scan_y - f64::from(ay)) / (f64::from(by) - f64::from(ay));
+            crossings.push(f64::from(ax) + t * (f64::from(bx) - f64::from(ax)));
+          }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal));
+        // Paint the pixels whose centers are between each pair of crossings.
+        for pair in crossings.chunks(2) {
+          if pair.len() == 2 {
+            fill_span(self, (pair[0] - 0.5).ceil() as i64, (pair[1] - 0.5).ceil() as i64 - 1, i64::from(y), fill);
+          }
+        }
+      }
+    }
+
+    if let Some(value) = style.stroke {
+      for (index, &(ax, ay)) in points.iter().enumerate() {
+        let (bx, by) = points[(index + 1) % points.len()];
+        self.draw_line(ax, ay, bx, by, value);
+      }
+    }
+  }
+
+  /// Paints the region of pixels with the same value as the pixel at (`x`, `y`), connected to it
+  /// horizontally or vertically, with `fill`.
+  fn flood_fill(&mut self, x: i32, y: i32, fill: Fill) {
+    let target = match self.get_pixel(x, y) {
+      Some(value) => value,
+      None => return,
+    };
+
+    // Track visited pixels, as a pattern may leave pixels with the target value.
+    let mut visited = vec![false; WIDTH * HEIGHT];
+    let mut pending = vec![(x, y)];
+    while let Some((x, y)) = pending.pop() {
+      if self.get_pixel(x, y) != Some(target) {
+        continue;
+      }
+      let index = y as usize * WIDTH + x as usize;
+      if visited[index] {
+        continue;
+      }
+      visited[index] = true;
+      fill_pixel(self, x, y, fill);
+      pending.push((x + 1, y));
+      pending.push((x - 1, y));
+      pending.push((x, y + 1));
+      pending.push((x, y - 1));
+    }
+  }
+
+  /// Draws `text` in `font` with the top of its first line at `y`, aligned to `x` as set by
+  /// `style`. Returns the height of the lines drawn in pixels, after wrapping.
+  fn draw_text<F: Font + ?Sized>(&mut self, font: &F, text: &str, x: i32, y: i32, style: &TextStyle) -> i32 {
+    text::draw_text(self, font, text, x, y, style)
+  }
+
+  /// Draws `text` in the anti-aliased `font` as `draw_text` does, blending `style.value` over
+  /// the existing pixels in proportion to the coverage of each glyph.
+  fn draw_text_antialiased<F: CoverageFont + ?Sized>(&mut self, font: &F, text: &str, x: i32, y: i32, style: &TextStyle) -> i32 {
+    antialias::draw_text(self, font, text, x, y, style)
+  }
+
+  /// Blends `value` over the pixel at (`x`, `y`) in proportion to `coverage`, from `0`
+  /// (unchanged) to `255` (replaced with `value`).
+  fn blend_pixel(&mut self, x: i32, y: i32, value: u8, coverage: u8) {
+    antialias::blend_pixel(self, x, y, value, coverage)
+  }
+
+}
+
+impl<T: FrameBuffer + ?Sized> Canvas for T {}
+
+/// Clips the line from (`x0`, `y0`) to (`x1`, `y1`) to the frame, so that it can be traced
+/// one pixel at a time without overflow. Lines within the frame are returned unchanged, and
+/// endpoints outside it are moved along the line to the nearest pixel on the frame edge.
+///
+/// # Returns
+/// The endpoints of the clipped line, or `None` if the line does not cross the frame.
+fn clip_line(x0: i32, y0: i32, x1: i32, y1: i32) -> Option<(i32, i32, i32, i32)> {
+  let (max_x, max_y) = ((WIDTH - 1) as f64, (HEIGHT - 1) as f64);
+  let within = |x: i32, y: i32| x >= 0 && y >= 0 && f64::from(x) <= max_x && f64::from(y) <= max_y;
+  if within(x0, y0) && within(x1, y1) {
+    return Some((x0, y0, x1, y1));
+  }
+
+  // Liang-Barsky clipping of the parametric line `p0 + t * (p1 - p0)` for `t` in `0...1`.
+  let (start_x, start_y) = (f64::from(x0), f64::from(y0));
+  let (dx, dy) = (f64::from(x1) - start_x, f64::from(y1) - start_y);
+  let (mut t0, mut t1) = (0.0f64, 1.0f64);
+  let edges = [(-dx, start_x), (dx, max_x - start_x), (-dy, start_y), (dy, max_y - start_y)];
+  for &(p, q) in edges.iter() {
+    if p == 0.0 {
+      if q < 0.0 {
+        return None;
+      }
+    } else {
+      let t = q / p;
+      if p < 0.0 {
+        t0 = t0.max(t);
+      } else {
+        t1 = t1.min(t);
+      }
+    }
+  }
+  if t0 > t1 {
+    return None;
+  }
+  let point = |t: f64| ((start_x + t * dx).round() as i32, (start_y + t * dy).round() as i32);
+  let ((x0, y0), (x1, y1)) = (point(t0), point(t1));
+  Some((x0, y0, x1, y1))
+}
+
+/// Paints the pixel at (`x`, `y`) of `buffer` if it is selected by the pattern of `fill`.
+fn fill_pixel<T: FrameBuffer + ?Sized>(buffer: &mut T, x: i32, y: i32, fill: Fill) {
+  if fill.pattern.contains(x, y) {
+    buffer.set_pixel(x, y, fill.value);
+  }
+}
+
+/// Sets the pixel at (`x`, `y`) of `buffer` to `value`, if it is within the image.
+fn plot<T: FrameBuffer + ?Sized>(buffer: &mut T, x: i64, y: i64, value: u8) {
+  if x >= 0 && y >= 0 && x < WIDTH as i64 && y < HEIGHT as i64 {
+    buffer.set_pixel(x as i32, y as i32, value);
+  }
+}
+
+/// Returns the part of the span of rows or columns from `low` to `high` inclusive which is
+/// within an image `size` pixels across.
+fn visible_span(low: i64, high: i64, size: usize) -> RangeInclusive<i64> {
+  low.max(0) ..= high.min(size as i64 - 1)
+}
+
+/// Paints the pixels from `x0` to `x1` inclusive of row `y` of `buffer` with `fill`.
+fn fill_span<T: FrameBuffer + ?Sized>(buffer: &mut T, x0: i64, x1: i64, y: i64, fill: Fill) {
+  if y < 0 || y >= HEIGHT as i64 {
+    return;
+  }
+  for x in visible_span(x0, x1, WIDTH) {
+    fill_pixel(buffer, x as i32, y as i32, fill);
+  }
+}
+
+/// Returns the points `(x, y)` of the octant of a circle of `radius` from the positive X axis
+/// to the diagonal, as produced by the midpoint circle algorithm. The other octants are
+/// obtained by swapping and negating the coordinates.
+///
+/// Only the points which may be within the image once placed around one of `centers` are
+/// returned: those whose `y`, as a row or a column, is within the image, so that the number of
+/// points is bounded by the size of the image rather than the radius.
+fn circle_octant(radius: i64, centers: &[(i64, i64)]) -> Vec<(i64, i64)> {
+  let mut offsets: Vec<i64> = centers.iter()
+    .flat_map(|&(cx, cy)| {
+      let rows = visible_span(cy - radius, cy + radius, HEIGHT).map(move |y| y - cy);
+      let columns = visible_span(cx - radius, cx + radius, WIDTH).map(move |x| x - cx);
+      rows.chain(columns)
+    })
+    .map(i64::abs)
+    .collect();
+  offsets.sort_unstable();
+  offsets.dedup();
+  offsets.into_iter()
+    .map(|y| (octant_x(radius, y), y))
+    .filter(|&(x, y)| x >= y)
+    .collect()
+}
+
+/// Returns the X coordinate of the point `y` rows from the center in the octant of a circle of
+/// `radius` traced by the midpoint circle algorithm: the largest `x` for which
+/// `x * x - x < radius * radius - y * y`.
+fn octant_x(radius: i64, y: i64) -> i64 {
+  let limit = radius * radius - y * y;
+  let mut x = (limit.max(0) as f64).sqrt() as i64 + 2;
+  while x > 0 && x * x - x >= limit {
+    x -= 1;
+  }
+  x
+}
+
+/// Returns the horizontal distance from the center to the edge of a filled circle of `radius`,
+/// `dy` rows from its center, consistent with the outline of `circle_octant`.
+fn circle_half_width(radius: i64, dy: i64) -> i64 {
+  if dy > radius {
+    return -1;
+  }
+  let limit = radius * radius + radius - dy * dy;
+  let mut half_width = (limit.max(0) as f64).sqrt() as i64 + 1;
+  while half_width * half_width > limit {
+    half_width -= 1;
+  }
+  half_width
+}
+
+/// Returns the horizontal distance from the center to the edge of a filled ellipse with radii
+/// `rx` and `ry`, `dy` rows from its center.
+fn ellipse_half_width(rx: i64, ry: i64, dy: i64) -> i64 {
+  if ry == 0 {
+    return if dy == 0 { rx } else { -1 };
+  }
+  let ratio = dy as f64 / ry as f64;
+  if ratio.abs() > 1.0 {
+    return -1;
+  }
+  (rx as f64 * (1.0 - ratio * ratio).sqrt()).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use framebuffer::{FrameBufferL1, FrameBufferL4};
+
+  /// Returns the number of pixels of `buffer` with a non-zero value.
+  fn lit_pixels<T: FrameBuffer>(buffer: &T) -> usize {
+    let mut count = 0;
+    for y in 0 .. HEIGHT as i32 {
+      for x in 0 .. WIDTH as i32 {
+        if buffer.get_pixel(x, y) != Some(0) {
+          count += 1;
+        }
+      }
+    }
+    count
+  }
+
+  #[test]
+  fn test_line() {
+    let mut buffer = FrameBufferL4::new();
+    buffer.draw_line(0, 0, 7, 3, 0xF);
+    assert_eq!(lit_pixels(&buffer), 8);
+    assert_eq!(buffer.get_pixel(0, 0), Some(0xF));
+    assert_eq!(buffer.get_pixel(7, 3), Some(0xF));
+
+    // Lines are symmetric in their end points.
+    let mut reversed = FrameBufferL4::new();
+    reversed.draw_line(7, 3, 0, 0, 0xF);
+    assert_eq!(&buffer.as_frame()[0..4], &reversed.as_frame()[0..4]);
+
+    // Lines are clipped to the image.
+    let mut clipped = FrameBufferL1::new();
+    clipped.draw_line(-10, 5, 200, 5, 1);
+    assert_eq!(lit_pixels(&clipped), 128);
+
+    // Extreme end points are clipped before the line is traced.
+    let mut extreme = FrameBufferL1::new();
+    extreme.draw_line(i32::MIN, i32::MIN, i32::MAX, i32::MAX, 1);
+    assert_eq!(lit_pixels(&extreme), 64);
+    assert_eq!(extreme.get_pixel(63, 63), Some(1));
+    extreme.clear();
+    extreme.draw_line(i32::MIN, -1, i32::MAX, -1, 1);
+    extreme.draw_line(i32::MAX, i32::MIN, i32::MAX, i32::MAX, 1);
+    assert_eq!(lit_pixels(&extreme), 0);
+  }
+
+  #[test]
+  fn test_rect() {
+    let mut buffer = FrameBufferL4::new();
+    buffer.draw_rect(2, 2, 11, 6, Style::stroke(0x8));
+    assert_eq!(lit_pixels(&buffer), 2 * 10 + 2 * 3);
+    assert_eq!(buffer.get_pixel(5, 4), Some(0));
+
+    buffer.draw_rect(11, 6, 2, 2, Style::stroke_and_fill(0x8, Fill::solid(0x3)));
+    assert_eq!(lit_pixels(&buffer), 10 * 5);
+    assert_eq!(buffer.get_pixel(5, 4), Some(0x3));
+    assert_eq!(buffer.get_pixel(2, 4), Some(0x8));
+
+    // Only the rows within the image are filled.
+    let mut extreme = FrameBufferL1::new();
+    extreme.draw_rect(0, -2_000_000_000, 10, 2_000_000_000, Style::fill(Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 11 * 64);
+    extreme.clear();
+    extreme.draw_rect(i32::MIN, i32::MIN, i32::MAX, i32::MAX, Style::stroke_and_fill(1, Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 128 * 64);
+  }
+
+  #[test]
+  fn test_fill_pattern() {
+    let mut buffer = FrameBufferL1::new();
+    buffer.draw_rect(0, 0, 15, 15, Style::fill(Fill { value: 1, pattern: Pattern::CHECKERBOARD }));
+    assert_eq!(lit_pixels(&buffer), 128);
+    assert_eq!(buffer.get_pixel(0, 0), Some(1));
+    assert_eq!(buffer.get_pixel(1, 0), Some(0));
+    assert_eq!(buffer.get_pixel(0, 1), Some(0));
+    assert_eq!(buffer.get_pixel(1, 1), Some(1));
+  }
+
+  #[test]
+  fn test_circle() {
+    let mut buffer = FrameBufferL1::new();
+    buffer.draw_circle(64, 32, 10, Style::stroke(1));
+    for &(x, y) in [(74, 32), (54, 32), (64, 42), (64, 22)].iter() {
+      assert_eq!(buffer.get_pixel(x, y), Some(1));
+    }
+    assert_eq!(buffer.get_pixel(64, 32), Some(0));
+
+    // Filling covers the interior up to the outline.
+    let mut filled = FrameBufferL1::new();
+    filled.draw_circle(64, 32, 10, Style::fill(Fill::solid(1)));
+    for y in 0 .. HEIGHT as i32 {
+      for x in 0 .. WIDTH as i32 {
+        if buffer.get_pixel(x, y) == Some(1) {
+          assert_eq!(filled.get_pixel(x, y), Some(1), "({}, {})", x, y);
+        }
+      }
+    }
+    let area = lit_pixels(&filled) as f64;
+    assert!((area - ::std::f64::consts::PI * 10.5 * 10.5).abs() < 20.0, "area {}", area);
+
+    // Circles far larger than the image are clipped to it.
+    let mut extreme = FrameBufferL1::new();
+    extreme.draw_circle(10, 10, 50_000, Style::fill(Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 128 * 64);
+    extreme.clear();
+    extreme.draw_circle(10, 10, 50_000, Style::stroke(1));
+    extreme.draw_circle(i32::MIN, i32::MAX, i32::MAX, Style::stroke_and_fill(1, Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 0);
+    extreme.draw_circle(64, 1_000_032, 1_000_000, Style::stroke(1));
+    assert_eq!(extreme.get_pixel(64, 32), Some(1));
+    assert_eq!(extreme.get_pixel(64, 31), Some(0));
+  }
+
+  #[test]
+  fn test_ellipse() {
+    let mut buffer = FrameBufferL1::new();
+    buffer.draw_ellipse(64, 32, 20, 8, Style::stroke(1));
+    for &(x, y) in [(84, 32), (44, 32), (64, 40), (64, 24)].iter() {
+      assert_eq!(buffer.get_pixel(x, y), Some(1));
+    }
+    assert_eq!(buffer.get_pixel(85, 32), Some(0));
+    assert_eq!(buffer.get_pixel(64, 41), Some(0));
+
+    let mut filled = FrameBufferL1::new();
+    filled.draw_ellipse(64, 32, 20, 8, Style::fill(Fill::solid(1)));
+    assert_eq!(filled.get_pixel(64, 32), Some(1));
+    assert_eq!(filled.get_pixel(84, 32), Some(1));
+    assert_eq!(filled.get_pixel(84, 30), Some(0));
+
+    // Ellipses far larger than the image are clipped to it.
+    let mut extreme = FrameBufferL1::new();
+    extreme.draw_ellipse(64, 32, i32::MAX, i32::MAX, Style::fill(Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 128 * 64);
+    extreme.clear();
+    extreme.draw_ellipse(64, 32, i32::MAX, i32::MAX, Style::stroke(1));
+    assert_eq!(lit_pixels(&extreme), 0);
+    extreme.draw_ellipse(64, i32::MAX, i32::MAX, i32::MAX - 32, Style::stroke(1));
+    assert_eq!(extreme.get_pixel(64, 32), Some(1));
+  }
+
+  #[test]
+  fn test_rounded_rect() {
+    let mut buffer = FrameBufferL1::new();
+    buffer.draw_rounded_rect(10, 10, 30, 20, 4, Style::stroke(1));
+    assert_eq!(buffer.get_pixel(10, 10), Some(0));
+    assert_eq!(buffer.get_pixel(14, 10), Some(1));
+    assert_eq!(buffer.get_pixel(10, 15), Some(1));
+
+    let mut filled = FrameBufferL1::new();
+    filled.draw_rounded_rect(10, 10, 30, 20, 4, Style::fill(Fill::solid(1)));
+    assert_eq!(filled.get_pixel(10, 10), Some(0));
+    assert_eq!(filled.get_pixel(30, 20), Some(0));
+    assert_eq!(filled.get_pixel(20, 10), Some(1));
+    assert_eq!(filled.get_pixel(20, 15), Some(1));
+
+    // Rectangles far larger than the image are clipped to it.
+    let mut extreme = FrameBufferL1::new();
+    extreme.draw_rounded_rect(i32::MIN, 0, i32::MAX, 10, i32::MAX, Style::fill(Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 128 * 11);
+    extreme.clear();
+    extreme.draw_rounded_rect(i32::MIN, 0, i32::MAX, 10, i32::MAX, Style::stroke(1));
+    assert_eq!(lit_pixels(&extreme), 128 * 2);
+    extreme.clear();
+    extreme.draw_rounded_rect(i32::MIN, i32::MIN, i32::MAX, i32::MAX, i32::MAX, Style::stroke(1));
+    assert_eq!(lit_pixels(&extreme), 0);
+  }
+
+  #[test]
+  fn test_arc() {
+    // A quarter arc from the right to the bottom of the circle.
+    let mut buffer = FrameBufferL1::new();
+    buffer.draw_arc(64, 32, 10, 0, 90, Style::stroke(1));
+    assert_eq!(buffer.get_pixel(74, 32), Some(1));
+    assert_eq!(buffer.get_pixel(64, 42), Some(1));
+    assert_eq!(buffer.get_pixel(54, 32), Some(0));
+    assert_eq!(buffer.get_pixel(64, 22), Some(0));
+
+    // Sectors may wrap around the positive X axis.
+    let mut filled = FrameBufferL1::new();
+    filled.draw_arc(64, 32, 10, 270, 450, Style::fill(Fill::solid(1)));
+    assert_eq!(filled.get_pixel(70, 32), Some(1));
+    assert_eq!(filled.get_pixel(64, 25), Some(1));
+    assert_eq!(filled.get_pixel(64, 39), Some(1));
+    assert_eq!(filled.get_pixel(58, 32), Some(0));
+
+    // Arcs far larger than the image are clipped to it.
+    let mut extreme = FrameBufferL1::new();
+    extreme.draw_arc(i32::MIN, i32::MIN, i32::MAX, i32::MIN, i32::MAX, Style::stroke_and_fill(1, Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 0);
+    extreme.draw_arc(64, 32, 50_000, 0, 90, Style::fill(Fill::solid(1)));
+    assert_eq!(lit_pixels(&extreme), 64 * 32);
+  }
+
+  #[test]
+  fn test_polygon() {
+    let triangle = [(10, 10), (30, 10), (10, 30)];
+    let mut buffer = FrameBufferL1::new();
+    buffer.draw_polygon(&triangle, Style::stroke(1));
+    assert_eq!(buffer.get_pixel(20, 10), Some(1));
+    assert_eq!(buffer.get_pixel(10, 20), Some(1));
+    assert_eq!(buffer.get_pixel(20, 20), Some(1));
+    assert_eq!(buffer.get_pixel(15, 15), Some(0));
+
+    let mut filled = FrameBufferL1::new();
+    filled.draw_polygon(&triangle, Style::fill(Fill::solid(1)));
+    assert_eq!(filled.get_pixel(15, 15), Some(1));
+    assert_eq!(filled.get_pixel(25, 25), Some(0));
+    assert_eq!(lit_pixels(&filled), 190);
+
+    // Empty polygons are ignored.
+    filled.draw_polygon(&[], Style::fill(Fill::solid(0)));
+  }
+
+  #[test]
+  fn test_flood_fill() {
+    let mut buffer = FrameBufferL4::new();
+    buffer.draw_rect(10, 10, 20, 20, Style::stroke(0xF));
+    buffer.flood_fill(15, 15, Fill::solid(0x5));
+    assert_eq!(buffer.get_pixel(15, 15), Some(0x5));
+    assert_eq!(buffer.get_pixel(11, 19), Some(0x5));
+    assert_eq!(buffer.get_pixel(10, 15), Some(0xF));
+    assert_eq!(buffer.get_pixel(5, 5), Some(0x0));
+
+    // Patterned fills terminate even though unpainted pixels keep the target value.
+    buffer.flood_fill(0, 0, Fill { value: 0x5, pattern: Pattern::CHECKERBOARD });
+    assert_eq!(buffer.get_pixel(0, 0), Some(0x5));
+    assert_eq!(buffer.get_pixel(1, 0), Some(0x0));
+    assert_eq!(buffer.get_pixel(15, 15), Some(0x5));
+
+    // Starting outside of the image does nothing.
+    buffer.flood_fill(-1, 0, Fill::solid(0xF));
+  }
+
+}
@@ -0,0 +1,905 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::{error, fmt, fs, io, path};
+
+use commands::*;
+use config::{ComRemap, CurrentRange, AddressIncrement, Remap};
+use gray::{gray_table_pulse_widths, GrayTablePreset};
+use panel::PanelProfile;
+use snapshot;
+use {ControlChannel, DisplayMode, HEIGHT, RAM_ROWS, WIDTH};
+
+/// Number of columns of display RAM, each holding two pixels.
+const RAM_COLUMNS: usize = 64;
+
+/// How the 64 rows of an emulated panel are wired to the 80 COM outputs of the display.
+///
+/// COM outputs are numbered as the display scans them, before COM split odd/even assigns them
+/// to pins, so the same glass is described identically whatever the remap.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Glass {
+  /// COM output driving the top row of the panel.
+  pub top_com: u8,
+  /// Whether each row below the top row is driven by the COM output numbered one lower, rather
+  /// than one higher.
+  pub descending: bool,
+}
+
+impl Glass {
+
+  /// Returns the glass of `profile`, derived from its display configuration: the profile shows
+  /// the first row of display RAM upright at the top of the panel, so the top row is wired to
+  /// the COM output the configuration scans first, and the rows below follow the scan.
+  pub fn for_panel(profile: PanelProfile) -> Glass {
+    let config = profile.config();
+    let offset = config.display_offset as usize;
+    match config.remap.com_remap {
+      ComRemap::TopDown => Glass { top_com: ((RAM_ROWS as usize - offset) % RAM_ROWS as usize) as u8, descending: false },
+      ComRemap::BottomUp => Glass { top_com: ((RAM_ROWS as usize - 1 + offset) % RAM_ROWS as usize) as u8, descending: true },
+    }
+  }
+
+  /// Returns the COM output driving `row` of the panel.
+  fn com(&self, row: usize) -> usize {
+    let rows = RAM_ROWS as usize;
+    if self.descending {
+      (self.top_com as usize + rows - row % rows) % rows
+    } else {
+      (self.top_com as usize + row) % rows
+    }
+  }
+
+}
+
+/// How the contents of display RAM are shown, as selected by the Set Display Mode commands.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum ViewMode {
+  /// Display RAM is shown as-is.
+  Normal,
+  /// Every pixel is lit at the highest gray level.
+  AllOn,
+  /// Every pixel is off.
+  AllOff,
+  /// Display RAM is shown with each gray level inverted.
+  Inverse,
+}
+
+/// Horizontal scroll set up with the Horizontal Scroll Setup command.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct HorizontalScroll {
+  /// Number of columns of two pixels moved per step.
+  pub columns_per_step: u8,
+  /// First row of display RAM scrolled.
+  pub start_row: u8,
+  /// Number of rows of display RAM scrolled.
+  pub rows: u8,
+  /// Time interval between steps, as sent to the display.
+  pub interval: u8,
+}
+
+/// Misuse of the display protocol detected by an emulator in strict mode.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum Violation {
+  /// Bytes were written while the control channel was in `DisplayMode::Reset`.
+  WriteDuringReset,
+  /// `command` was sent with an `argument` outside the range documented in the datasheet.
+  ArgumentOutOfRange {
+    command: u8,
+    argument: u8,
+  },
+  /// The control channel left command mode before all of the arguments of `command` were
+  /// received, splitting the command across separate sequences.
+  SplitCommand {
+    command: u8,
+  },
+  /// Display data was written after a reset, before the display was initialized with the Set
+  /// Re-Map command, which decides how the data is laid out in display RAM, or turned on.
+  DataBeforeInit,
+}
+
+impl fmt::Display for Violation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Violation::WriteDuringReset =>
+        write!(f, "bytes written while the display is held in reset"),
+      Violation::ArgumentOutOfRange { command, argument } =>
+        write!(f, "argument {:#04X} of command {:#04X} is outside the documented range", argument, command),
+      Violation::SplitCommand { command } =>
+        write!(f, "command {:#04X} is split across separate sequences", command),
+      Violation::DataBeforeInit =>
+        write!(f, "display data written before the display was initialized"),
+    }
+  }
+}
+
+/// State of the emulated display, shared by every handle to the emulator.
+struct EmulatorState {
+  /// Wiring of the rows of the panel to the COM outputs.
+  glass: Glass,
+  /// Mode the control channel is in.
+  mode: DisplayMode,
+  /// Bytes of the command being received, until all of its arguments have been received.
+  pending: Vec<u8>,
+  /// Display RAM, 80 rows of 64 columns of two pixels.
+  gddram: [[u8; 64]; 80],
+  /// First and last column of the address window.
+  column_window: (u8, u8),
+  /// First and last row of the address window.
+  row_window: (u8, u8),
+  /// Column written by the next data byte.
+  column: u8,
+  /// Row written by the next data byte.
+  row: u8,
+  remap: Remap,
+  start_line: u8,
+  display_offset: u8,
+  multiplex_ratio: u8,
+  contrast: u8,
+  current_range: CurrentRange,
+  gray_table: [u8; 8],
+  view_mode: ViewMode,
+  on: bool,
+  graphic_acceleration: u8,
+  horizontal_scroll: Option<HorizontalScroll>,
+  scroll_active: bool,
+  /// Command bytes which are not part of the command set of the display.
+  unknown_commands: Vec<u8>,
+  /// Whether misuse of the display protocol is recorded in `violations`.
+  strict: bool,
+  /// Whether the display has been initialized or turned on since it was last reset.
+  initialized: bool,
+  violations: Vec<Violation>,
+}
+
+impl EmulatorState {
+
+  /// Returns the state of a display wired to `glass` after a reset, with display RAM cleared.
+  fn new(glass: Glass) -> Self {
+    let mut state = EmulatorState {
+      glass,
+      mode: DisplayMode::Idle,
+      pending: Vec::new(),
+      gddram: [[0u8; 64]; 80],
+      column_window: (0, 0),
+      row_window: (0, 0),
+      column: 0,
+      row: 0,
+      remap: Remap::from_byte(0x00),
+      start_line: 0,
+      display_offset: 0,
+      multiplex_ratio: 0,
+      contrast: 0,
+      current_range: CurrentRange::Quarter,
+      gray_table: [0; 8],
+      view_mode: ViewMode::Normal,
+      on: false,
+      graphic_acceleration: 0,
+      horizontal_scroll: None,
+      scroll_active: false,
+      unknown_commands: Vec::new(),
+      strict: false,
+      initialized: false,
+      violations: Vec::new(),
+    };
+    state.reset();
+    state
+  }
+
+  /// Returns the registers to their power-on values. Display RAM is left unchanged.
+  fn reset(&mut self) {
+    self.pending.clear();
+    self.column_window = (0, RAM_COLUMNS as u8 - 1);
+    self.row_window = (0, RAM_ROWS - 1);
+    self.column = 0;
+    self.row = 0;
+    self.remap = Remap::from_byte(0x00);
+    self.start_line = 0;
+    self.display_offset = 0;
+    self.multiplex_ratio = RAM_ROWS;
+    self.contrast = 0x40;
+    self.current_range = CurrentRange::Quarter;
+    self.gray_table = GrayTablePreset::Linear.table();
+    self.view_mode = ViewMode::Normal;
+    self.on = false;
+    self.graphic_acceleration = 0;
+    self.horizontal_scroll = None;
+    self.scroll_active = false;
+    self.initialized = false;
+  }
+
+  /// Records `violation` in strict mode, unless it repeats the last violation recorded.
+  fn flag(&mut self, violation: Violation) {
+    if self.strict && self.violations.last() != Some(&violation) {
+      self.violations.push(violation);
+    }
+  }
+
+  /// Flags an argument of `command` outside the range documented in the datasheet.
+  fn check_arguments(&mut self, command: &[u8]) {
+    let argument = match command.get(1) {
+      Some(&argument) => argument,
+      None => return,
+    };
+    let in_range = match command[0] {
+      SETCONTRAST => argument <= 0x7F,
+      SETROWPERIOD => argument >= 0x14,
+      SETSTARTLINE => argument < RAM_ROWS,
+      // Only the levels for a disconnected VSL pin and for a capacitor to VSS are documented.
+      SETVSL => argument == 0x02 || argument == 0x0E,
+      _ => true,
+    };
+    if !in_range {
+      self.flag(Violation::ArgumentOutOfRange { command: command[0], argument });
+    }
+  }
+
+  /// Handles a byte received over the transport in the current mode.
+  fn receive(&mut self, byte: u8) {
+    match self.mode {
+      DisplayMode::Command => {
+        self.pending.push(byte);
+        if self.pending.len() > argument_count(self.pending[0]) {
+          let command = ::std::mem::take(&mut self.pending);
+          self.check_arguments(&command);
+          self.execute(&command);
+        }
+      }
+      DisplayMode::Data => {
+        if !self.initialized {
+          self.flag(Violation::DataBeforeInit);
+        }
+        self.write_data(byte);
+      }
+      DisplayMode::Reset => self.flag(Violation::WriteDuringReset),
+      DisplayMode::Idle => {}
+    }
+  }
+
+  /// Executes a `command` followed by all of its arguments.
+  fn execute(&mut self, command: &[u8]) {
+    let arguments = &command[1 ..];
+    match command[0] {
+      SETCOLADDR => {
+        self.column_window = (arguments[0] & 0x3F, arguments[1] & 0x3F);
+        self.column = self.column_window.0;
+      }
+      SETROWADDR => {
+        self.row_window = (arguments[0] & 0x7F, arguments[1] & 0x7F);
+        self.row = self.row_window.0;
+      }
+      SETCONTRAST => self.contrast = arguments[0] & 0x7F,
+      SETCURRENT_QUARTER => self.current_range = CurrentRange::Quarter,
+      SETCURRENT_HALF => self.current_range = CurrentRange::Half,
+      SETCURRENT_FULL => self.current_range = CurrentRange::Full,
+      SETREMAP => {
+        self.remap = Remap::from_byte(arguments[0]);
+        self.initialized = true;
+      }
+      SETSTARTLINE => self.start_line = arguments[0] & 0x7F,
+      SETOFFSET => self.display_offset = arguments[0] & 0x7F,
+      NORMALDISPLAY => self.view_mode = ViewMode::Normal,
+      ALLONDISPLAY => self.view_mode = ViewMode::AllOn,
+      ALLOFFDISPLAY => self.view_mode = ViewMode::AllOff,
+      INVERTDISPLAY => self.view_mode = ViewMode::Inverse,
+      SETMULTIPLEX => self.multiplex_ratio = (arguments[0] & 0x7F) + 1,
+      DISPLAYOFF => self.on = false,
+      DISPLAYON => {
+        self.on = true;
+        self.initialized = true;
+      }
+      SETGRAYTABLE => self.gray_table.copy_from_slice(arguments),
+      GFXACCEL => self.graphic_acceleration = arguments[0],
+      DRAWRECT => self.draw_rect(arguments),
+      COPY => self.copy(arguments),
+      SETUPHSCROLL => {
+        self.horizontal_scroll = Some(HorizontalScroll {
+          columns_per_step: arguments[0],
+          start_row: arguments[1],
+          rows: arguments[2],
+          interval: arguments[3],
+        });
+      }
+      DEACTIVATESCROLL => self.scroll_active = false,
+      ACTIVATESCROLL => self.scroll_active = true,
+      // Configuration of the drive circuitry does not affect the contents of the display.
+      MASTERCONFIG | SETPRECHARGECOMPENABLE | SETPHASELEN | SETROWPERIOD | SETCLOCK |
+      SETPRECHARGECOMP | SETVCOMLEVEL | SETVSL | NOP => {}
+      unknown => self.unknown_commands.push(unknown),
+    }
+  }
+
+  /// Writes a byte of pixel data at the current address, then advances the address within the
+  /// address window.
+  fn write_data(&mut self, byte: u8) {
+    let (column, row) = (self.column as usize % RAM_COLUMNS, self.row as usize % RAM_ROWS as usize);
+    self.gddram[row][column] = byte;
+
+    match self.remap.address_increment {
+      AddressIncrement::Horizontal => {
+        if advance(&mut self.column, self.column_window) {
+          advance(&mut self.row, self.row_window);
+        }
+      }
+      AddressIncrement::Vertical => {
+        if advance(&mut self.row, self.row_window) {
+          advance(&mut self.column, self.column_window);
+        }
+      }
+    }
+  }
+
+  /// Draws a rectangle of display RAM with the Draw Rectangle command, filling it if the fill
+  /// option of the graphic acceleration command is set.
+  fn draw_rect(&mut self, arguments: &[u8]) {
+    let (column0, row0) = (arguments[0] as usize, arguments[1] as usize);
+    let (column1, row1) = (arguments[2] as usize, arguments[3] as usize);
+    let fill = (self.graphic_acceleration & GFXACCEL_FILL) != 0;
+    for row in row0 ..= row1.min(RAM_ROWS as usize - 1) {
+      for column in column0 ..= column1.min(RAM_COLUMNS - 1) {
+        let edge = row == row0 || row == row1 || column == column0 || column == column1;
+        if fill || edge {
+          self.gddram[row][column] = arguments[4];
+        }
+      }
+    }
+  }
+
+  /// Copies a rectangle of display RAM with the Copy command, applying the reverse and
+  /// wrap-around options of the graphic acceleration command.
+  fn copy(&mut self, arguments: &[u8]) {
+    let (column0, row0) = (arguments[0] as usize, arguments[1] as usize);
+    let (column1, row1) = (arguments[2] as usize, arguments[3] as usize);
+    let (new_column, new_row) = (arguments[4] as usize, arguments[5] as usize);
+    let reverse = (self.graphic_acceleration & GFXACCEL_REVERSE) != 0;
+    let wrap = (self.graphic_acceleration & GFXACCEL_WRAP) != 0;
+
+    let source = self.gddram;
+    for row in 0 ..= row1.saturating_sub(row0) {
+      for column in 0 ..= column1.saturating_sub(column0) {
+        let (source_row, source_column) = (row0 + row, column0 + column);
+        let (target_row, mut target_column) = (new_row + row, new_column + column);
+        if source_row >= RAM_ROWS as usize || source_column >= RAM_COLUMNS || target_row >= RAM_ROWS as usize {
+          continue;
+        }
+        if target_column >= RAM_COLUMNS {
+          if !wrap {
+            continue;
+          }
+          target_column %= RAM_COLUMNS;
+        }
+        let byte = source[source_row][source_column];
+        self.gddram[target_row][target_column] = if reverse { !byte } else { byte };
+      }
+    }
+  }
+
+  /// Returns the row of display RAM shown on `row` of the panel, if it is scanned.
+  ///
+  /// Each of the rows scanned in turn, as many as the multiplex ratio, shows the next row of
+  /// display RAM from the start line. Scanning top-down, COM output `c` shows the row scanned
+  /// `c` plus the display offset rows into the scan, and scanning bottom-up reverses the COM
+  /// outputs across all 80 of them.
+  fn ram_row(&self, row: usize) -> Option<usize> {
+    let rows = RAM_ROWS as usize;
+    let com = match self.remap.com_remap {
+      ComRemap::TopDown => self.glass.com(row),
+      ComRemap::BottomUp => rows - 1 - self.glass.com(row),
+    };
+    let scanned = (com + self.display_offset as usize) % rows;
+    if scanned >= self.multiplex_ratio as usize {
+      return None;
+    }
+    Some((self.start_line as usize + scanned) % rows)
+  }
+
+  /// Returns the gray level shown by the pixel at (`x`, `y`) of the panel.
+  fn visible_pixel(&self, x: usize, y: usize) -> u8 {
+    if !self.on {
+      return 0x0;
+    }
+    let level = match self.ram_row(y) {
+      Some(row) => {
+        let column = if self.remap.column_remap { RAM_COLUMNS - 1 - x / 2 } else { x / 2 };
+        let byte = self.gddram[row][column];
+        if (x & 1 == 0) != self.remap.nibble_remap { byte >> 4 } else { byte & 0x0F }
+      }
+      None => 0x0,
+    };
+    match self.view_mode {
+      ViewMode::Normal => level,
+      ViewMode::AllOn => 0xF,
+      ViewMode::AllOff => 0x0,
+      ViewMode::Inverse => 0xF - level,
+    }
+  }
+
+}
+
+/// Advances an `address` within `window`, wrapping around to its start.
+/// Returns whether the address wrapped around.
+fn advance(address: &mut u8, window: (u8, u8)) -> bool {
+  if *address >= window.1 {
+    *address = window.0;
+    true
+  } else {
+    *address += 1;
+    false
+  }
+}
+
+/// Returns the number of argument bytes following `command`.
+pub(crate) fn argument_count(command: u8) -> usize {
+  match command {
+    SETCONTRAST | SETREMAP | SETSTARTLINE | SETOFFSET | SETMULTIPLEX | MASTERCONFIG |
+    SETPRECHARGECOMPENABLE | SETPHASELEN | SETROWPERIOD | SETCLOCK | SETPRECHARGECOMP |
+    SETVCOMLEVEL | SETVSL | GFXACCEL => 1,
+    SETCOLADDR | SETROWADDR => 2,
+    SETUPHSCROLL => 4,
+    DRAWRECT => 5,
+    COPY => 6,
+    SETGRAYTABLE => 8,
+    _ => 0,
+  }
+}
+
+/// A software model of an SSD1325 display, for testing without hardware.
+///
+/// The emulator decodes the commands and pixel data sent to it into display RAM and the image
+/// shown on the panel. Each handle to the emulator is both a transport and a control channel,
+/// and handles made with `clone` share the same display, so one can be given to
+/// `Ssd1325::new` for each and another kept to inspect the display.
+///
+/// The panel is 128x64 pixels, with its rows wired to the COM outputs of the display as
+/// described by a `Glass`, by default that of the Adafruit and Newhaven modules, on which the
+/// default configuration shows display RAM upright. Display RAM holds pixel data as
+/// written, and the remap is applied as the panel is shown, so that column and nibble remapping
+/// together mirror the image horizontally. Horizontal scrolling only moves display RAM when the
+/// scroll is stepped with `step_horizontal_scroll`.
+///
+/// In strict mode, enabled with `set_strict`, the emulator also records misuse of the display
+/// protocol which the hardware would silently tolerate or misinterpret, as `Violation`s.
+#[derive(Clone)]
+pub struct Emulator {
+  state: Rc<RefCell<EmulatorState>>,
+}
+
+impl Emulator {
+
+  /// Returns an emulated display with the glass of the Adafruit module in its power-on state,
+  /// with display RAM cleared.
+  pub fn new() -> Self {
+    Emulator::with_glass(Glass::for_panel(PanelProfile::Adafruit27_128x64))
+  }
+
+  /// Returns an emulated display with its panel wired as `glass` in its power-on state, with
+  /// display RAM cleared.
+  pub fn with_glass(glass: Glass) -> Self {
+    Emulator { state: Rc::new(RefCell::new(EmulatorState::new(glass))) }
+  }
+
+  /// Returns how the rows of the panel are wired to the COM outputs.
+  pub fn glass(&self) -> Glass {
+    self.state.borrow().glass
+  }
+
+  /// Returns a copy of the 80 rows of display RAM, each holding 128 pixels packed 2 per byte.
+  pub fn gddram(&self) -> [[u8; 64]; 80] {
+    self.state.borrow().gddram
+  }
+
+  /// Returns the gray level of each pixel shown on the panel, as 64 rows of 128 pixels. The
+  /// image accounts for the remap, start line, display offset, multiplex ratio, display mode
+  /// and whether the display is on, but not the gray scale table or contrast.
+  pub fn visible_image(&self) -> [[u8; 128]; 64] {
+    let state = self.state.borrow();
+    let mut image = [[0u8; 128]; 64];
+    for (y, line) in image.iter_mut().enumerate().take(HEIGHT) {
+      for (x, pixel) in line.iter_mut().enumerate().take(WIDTH) {
+        *pixel = state.visible_pixel(x, y);
+      }
+    }
+    image
+  }
+
+  /// Returns the image shown on the panel in the format accepted by `Ssd1325::blit_l4`, so that
+  /// it can be compared with the frame sent to the display.
+  pub fn visible_frame(&self) -> [[u8; 64]; 64] {
+    let mut frame = [[0u8; 64]; 64];
+    for (line, packed) in self.visible_image().iter().zip(frame.iter_mut()) {
+      for (byte, pair) in packed.iter_mut().zip(line.chunks(2)) {
+        *byte = (pair[0] << 4) | pair[1];
+      }
+    }
+    frame
+  }
+
+  /// Returns the brightness of each pixel shown on the panel from `0` (off) to `255`, as 64 rows
+  /// of 128 pixels. Gray levels from `visible_image` are mapped through the pulse widths of the
+  /// gray scale table, relative to the pulse width of the highest gray level.
+  pub fn rendered_image(&self) -> [[u8; 128]; 64] {
+    let widths = gray_table_pulse_widths(&self.gray_table());
+    let full = u32::from(widths[15]);
+    let mut image = self.visible_image();
+    for pixel in image.iter_mut().flat_map(|line| line.iter_mut()) {
+      *pixel = (u32::from(widths[*pixel as usize]) * 255 + full / 2).checked_div(full).unwrap_or(0) as u8;
+    }
+    image
+  }
+
+  /// Returns the image from `rendered_image` encoded as a binary PGM file.
+  pub fn to_pgm(&self) -> Vec<u8> {
+    snapshot::encode_pgm(&snapshot::panel_image(&self.rendered_image()))
+  }
+
+  /// Writes the image from `rendered_image` to the file at `path` as a binary PGM image.
+  pub fn save_pgm<P: AsRef<path::Path>>(&self, path: P) -> Result<(),Box<dyn error::Error>> {
+    fs::write(path, self.to_pgm())?;
+    Ok(())
+  }
+
+  /// Asserts that the image from `rendered_image` matches the golden PGM image at `path`. This
+  /// is a helper for tests, which only exists with the `emulator` feature like the rest of the
+  /// emulator, so the driver never reads the environment or writes files on its own.
+  ///
+  /// On a mismatch, a visual diff is written next to the golden image, with the extension
+  /// `.diff.pgm`, showing the golden image, the rendered image, and the golden image dimmed
+  /// with differing pixels in white. When the `SSD1325_UPDATE_GOLDEN` environment variable is
+  /// set, the golden image is replaced with the rendered image instead.
+  ///
+  /// # Panics
+  /// If the images differ, or the golden image cannot be read or written.
+  pub fn assert_matches_golden<P: AsRef<path::Path>>(&self, path: P) {
+    if let Err(error) = snapshot::compare_golden(&self.rendered_image(), path.as_ref()) {
+      panic!("{}", error);
+    }
+  }
+
+  /// Returns whether the display is on.
+  pub fn is_on(&self) -> bool {
+    self.state.borrow().on
+  }
+
+  /// Returns how display RAM is shown.
+  pub fn view_mode(&self) -> ViewMode {
+    self.state.borrow().view_mode
+  }
+
+  /// Returns the last remap received.
+  pub fn remap(&self) -> Remap {
+    self.state.borrow().remap
+  }
+
+  /// Returns the row of display RAM the scan starts from.
+  pub fn start_line(&self) -> u8 {
+    self.state.borrow().start_line
+  }
+
+  /// Returns the display offset.
+  pub fn display_offset(&self) -> u8 {
+    self.state.borrow().display_offset
+  }
+
+  /// Returns the number of rows scanned.
+  pub fn multiplex_ratio(&self) -> u8 {
+    self.state.borrow().multiplex_ratio
+  }
+
+  /// Returns the contrast current.
+  pub fn contrast(&self) -> u8 {
+    self.state.borrow().contrast
+  }
+
+  /// Returns the current range.
+  pub fn current_range(&self) -> CurrentRange {
+    self.state.borrow().current_range
+  }
+
+  /// Returns the gray scale table.
+  pub fn gray_table(&self) -> [u8; 8] {
+    self.state.borrow().gray_table
+  }
+
+  /// Returns the options of the last graphic acceleration command.
+  pub fn graphic_acceleration(&self) -> u8 {
+    self.state.borrow().graphic_acceleration
+  }
+
+  /// Returns the horizontal scroll set up, if any.
+  pub fn horizontal_scroll(&self) -> Option<HorizontalScroll> {
+    self.state.borrow().horizontal_scroll
+  }
+
+  /// Returns whether horizontal scrolling is active.
+  pub fn is_scroll_active(&self) -> bool {
+    self.state.borrow().scroll_active
+  }
+
+  /// Returns the command bytes received which are not part of the command set of the display.
+  pub fn unknown_commands(&self) -> Vec<u8> {
+    self.state.borrow().unknown_commands.clone()
+  }
+
+  /// Sets whether misuse of the display protocol is recorded, to be inspected with
+  /// `violations`. Strict mode is off for new emulators.
+  pub fn set_strict(&self, strict: bool) {
+    self.state.borrow_mut().strict = strict;
+  }
+
+  /// Returns the misuse of the display protocol recorded in strict mode, in the order it
+  /// occurred. Consecutive repeats of the same violation, such as each byte of data written
+  /// before the display is initialized, are recorded once.
+  pub fn violations(&self) -> Vec<Violation> {
+    self.state.borrow().violations.clone()
+  }
+
+  /// Discards the violations recorded so far.
+  pub fn clear_violations(&self) {
+    self.state.borrow_mut().violations.clear();
+  }
+
+  /// Performs `steps` steps of the active horizontal scroll, rotating the scrolled rows of
+  /// display RAM to the right. Does nothing if scrolling is not active.
+  pub fn step_horizontal_scroll(&self, steps: usize) {
+    let mut state = self.state.borrow_mut();
+    let scroll = match state.horizontal_scroll {
+      Some(scroll) if state.scroll_active => scroll,
+      _ => return,
+    };
+    let shift = (scroll.columns_per_step as usize * steps) % RAM_COLUMNS;
+    let start = scroll.start_row as usize;
+    let end = (start + scroll.rows as usize).min(RAM_ROWS as usize);
+    for row in state.gddram[start.min(end) .. end].iter_mut() {
+      row.rotate_right(shift);
+    }
+  }
+
+}
+
+impl Default for Emulator {
+  fn default() -> Self {
+    Emulator::new()
+  }
+}
+
+impl io::Write for Emulator {
+  fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
+    let mut state = self.state.borrow_mut();
+    for &byte in data {
+      state.receive(byte);
+    }
+    Ok(data.len())
+  }
+
+  fn flush(&mut self) -> Result<(), io::Error> {
+    Ok(())
+  }
+}
+
+impl ControlChannel for Emulator {
+  fn run_in_mode(&mut self, mode: DisplayMode, f: &mut dyn FnMut() -> Result<(),Box<dyn error::Error>>) -> Result<(),Box<dyn error::Error>> {
+    {
+      let mut state = self.state.borrow_mut();
+      state.mode = mode;
+      if mode == DisplayMode::Reset {
+        state.reset();
+      }
+    }
+
+    let result = f();
+    let mut state = self.state.borrow_mut();
+    if let Some(&command) = state.pending.first() {
+      if state.mode == DisplayMode::Command {
+        state.flag(Violation::SplitCommand { command });
+      }
+    }
+    state.mode = DisplayMode::Idle;
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Write;
+
+  /// Sends `bytes` to `emulator` in `mode`.
+  fn send(emulator: &mut Emulator, mode: DisplayMode, bytes: &[u8]) {
+    let mut transport = emulator.clone();
+    emulator.run_in_mode(mode, &mut || {
+      transport.write_all(bytes)?;
+      Ok(())
+    }).unwrap();
+  }
+
+  #[test]
+  fn test_data_window() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Command, &[SETCOLADDR, 2, 3, SETROWADDR, 10, 11]);
+    send(&mut emulator, DisplayMode::Data, &[0x12, 0x34, 0x56, 0x78]);
+    let gddram = emulator.gddram();
+    assert_eq!(gddram[10][2 .. 4], [0x12, 0x34]);
+    assert_eq!(gddram[11][2 .. 4], [0x56, 0x78]);
+    assert_eq!(gddram[12][2], 0x00);
+
+    // The address wraps around to the start of the window.
+    send(&mut emulator, DisplayMode::Data, &[0x9A]);
+    assert_eq!(emulator.gddram()[10][2], 0x9A);
+  }
+
+  #[test]
+  fn test_vertical_increment() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Command, &[SETREMAP, 0x04, SETCOLADDR, 0, 1, SETROWADDR, 0, 1]);
+    send(&mut emulator, DisplayMode::Data, &[0x12, 0x34, 0x56]);
+    let gddram = emulator.gddram();
+    assert_eq!(gddram[0][0], 0x12);
+    assert_eq!(gddram[1][0], 0x34);
+    assert_eq!(gddram[0][1], 0x56);
+  }
+
+  #[test]
+  fn test_commands_across_writes() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Command, &[SETCONTRAST]);
+    send(&mut emulator, DisplayMode::Command, &[0x22, DISPLAYON, 0xFF]);
+    assert_eq!(emulator.contrast(), 0x22);
+    assert!(emulator.is_on());
+    assert_eq!(emulator.unknown_commands(), vec![0xFF]);
+
+    // Data sent outside of Data mode is ignored.
+    send(&mut emulator, DisplayMode::Idle, &[0xFF]);
+    assert_eq!(emulator.gddram(), [[0u8; 64]; 80]);
+  }
+
+  #[test]
+  fn test_reset() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Command, &[SETCONTRAST, 0x22, DISPLAYON, SETMULTIPLEX, 0x3F]);
+    send(&mut emulator, DisplayMode::Reset, &[]);
+    assert_eq!(emulator.contrast(), 0x40);
+    assert_eq!(emulator.multiplex_ratio(), 80);
+    assert!(!emulator.is_on());
+  }
+
+  #[test]
+  fn test_draw_rect_and_copy() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Command, &[DRAWRECT, 1, 1, 3, 3, 0x77]);
+    let gddram = emulator.gddram();
+    assert_eq!(gddram[1][1 ..= 3], [0x77; 3]);
+    assert_eq!(gddram[2][1 ..= 3], [0x77, 0x00, 0x77]);
+
+    send(&mut emulator, DisplayMode::Command, &[GFXACCEL, GFXACCEL_FILL, DRAWRECT, 1, 1, 3, 3, 0x11]);
+    assert_eq!(emulator.gddram()[2][1 ..= 3], [0x11; 3]);
+
+    send(&mut emulator, DisplayMode::Command, &[GFXACCEL, GFXACCEL_REVERSE | GFXACCEL_WRAP, COPY, 1, 1, 2, 1, 63, 5]);
+    let gddram = emulator.gddram();
+    assert_eq!(gddram[5][63], 0xEE);
+    assert_eq!(gddram[5][0], 0xEE);
+  }
+
+  #[test]
+  fn test_visible_image() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Command, &[SETMULTIPLEX, 0x3F, SETOFFSET, 0x4C, SETREMAP, 0x50]);
+    send(&mut emulator, DisplayMode::Data, &[0xAB]);
+    assert_eq!(emulator.visible_image()[0][0], 0x0);
+
+    // With the default configuration, display RAM is shown upright.
+    send(&mut emulator, DisplayMode::Command, &[DISPLAYON]);
+    let image = emulator.visible_image();
+    assert_eq!(image[0][0 .. 2], [0xA, 0xB]);
+    assert_eq!(emulator.visible_frame()[0][0], 0xAB);
+
+    // Flipping columns and nibbles mirrors horizontally.
+    send(&mut emulator, DisplayMode::Command, &[SETREMAP, 0x53]);
+    assert_eq!(emulator.visible_image()[0][126 .. 128], [0xB, 0xA]);
+
+    // Flipping rows with the offset adjusted for the multiplex ratio mirrors vertically.
+    send(&mut emulator, DisplayMode::Command, &[SETREMAP, 0x40, SETOFFSET, 0x44]);
+    assert_eq!(emulator.visible_image()[63][0 .. 2], [0xA, 0xB]);
+
+    // The start line scrolls display RAM up the panel.
+    send(&mut emulator, DisplayMode::Command, &[SETREMAP, 0x50, SETOFFSET, 0x4C, SETSTARTLINE, 79]);
+    assert_eq!(emulator.visible_image()[1][0 .. 2], [0xA, 0xB]);
+
+    send(&mut emulator, DisplayMode::Command, &[INVERTDISPLAY]);
+    assert_eq!(emulator.visible_image()[1][0 .. 3], [0x5, 0x4, 0xF]);
+    send(&mut emulator, DisplayMode::Command, &[ALLONDISPLAY]);
+    assert_eq!(emulator.visible_image(), [[0xF; 128]; 64]);
+  }
+
+  #[test]
+  fn test_glass() {
+    assert_eq!(Glass::for_panel(PanelProfile::Adafruit27_128x64), Glass { top_com: 75, descending: true });
+    assert_eq!(Emulator::new().glass(), Glass::for_panel(PanelProfile::Nhd27_12864));
+
+    // A panel wired top-down from the first COM output shows display RAM upright with no offset.
+    let mut emulator = Emulator::with_glass(Glass { top_com: 0, descending: false });
+    send(&mut emulator, DisplayMode::Command, &[SETMULTIPLEX, 0x3F, SETREMAP, 0x40, DISPLAYON]);
+    send(&mut emulator, DisplayMode::Data, &[0xAB]);
+    assert_eq!(emulator.visible_image()[0][0 .. 2], [0xA, 0xB]);
+
+    // The configuration of the Adafruit module scans this glass upside down, with the first row
+    // of display RAM below the panel.
+    send(&mut emulator, DisplayMode::Command, &[SETOFFSET, 0x4C, SETREMAP, 0x50]);
+    assert!(emulator.visible_image().iter().all(|line| line[0] == 0));
+  }
+
+  #[test]
+  fn test_rendered_image() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Command, &[SETMULTIPLEX, 0x3F, SETOFFSET, 0x4C, SETREMAP, 0x50, DISPLAYON]);
+    send(&mut emulator, DisplayMode::Data, &[0xAF]);
+
+    // Gray levels are shown in proportion to their pulse width in the gray scale table.
+    assert_eq!(emulator.rendered_image()[0][0 .. 3], [170, 255, 0]);
+    send(&mut emulator, DisplayMode::Command, &[SETGRAYTABLE, 0x01, 0x11, 0x22, 0x32, 0x43, 0x54, 0x65, 0x76]);
+    assert_eq!(emulator.rendered_image()[0][0 .. 3], [108, 255, 0]);
+
+    // Inversion is applied before the gray scale table.
+    send(&mut emulator, DisplayMode::Command, &[INVERTDISPLAY]);
+    assert_eq!(emulator.rendered_image()[0][0 .. 3], [28, 0, 255]);
+
+    let pgm = emulator.to_pgm();
+    assert!(pgm.starts_with(b"P5\n128 64\n255\n"));
+    assert_eq!(pgm[14 .. 17], [28, 0, 255]);
+  }
+
+  #[test]
+  fn test_strict_mode() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Reset, &[0x00]);
+    send(&mut emulator, DisplayMode::Command, &[SETCONTRAST, 0x80]);
+    assert_eq!(emulator.violations(), []);
+
+    emulator.set_strict(true);
+    send(&mut emulator, DisplayMode::Reset, &[0x00]);
+    send(&mut emulator, DisplayMode::Command, &[SETCONTRAST, 0x80, SETROWPERIOD, 0x13, SETSTARTLINE, 80, SETVSL, 0x0F]);
+    send(&mut emulator, DisplayMode::Command, &[SETCONTRAST, 0x7F, SETROWPERIOD, 0x14, SETSTARTLINE, 79, SETVSL, 0x0E]);
+    assert_eq!(emulator.violations(), [
+      Violation::WriteDuringReset,
+      Violation::ArgumentOutOfRange { command: SETCONTRAST, argument: 0x80 },
+      Violation::ArgumentOutOfRange { command: SETROWPERIOD, argument: 0x13 },
+      Violation::ArgumentOutOfRange { command: SETSTARTLINE, argument: 80 },
+      Violation::ArgumentOutOfRange { command: SETVSL, argument: 0x0F },
+    ]);
+    emulator.clear_violations();
+
+    // Arguments must be sent in the same sequence as their command.
+    send(&mut emulator, DisplayMode::Command, &[SETCOLADDR, 0]);
+    send(&mut emulator, DisplayMode::Command, &[63]);
+    assert_eq!(emulator.violations(), [Violation::SplitCommand { command: SETCOLADDR }]);
+    emulator.clear_violations();
+
+    // Data written before the display is initialized is flagged once per run of bytes. The
+    // display need not be on.
+    send(&mut emulator, DisplayMode::Data, &[0x12, 0x34]);
+    send(&mut emulator, DisplayMode::Data, &[0x56]);
+    send(&mut emulator, DisplayMode::Command, &[SETREMAP, 0x50]);
+    send(&mut emulator, DisplayMode::Data, &[0x78]);
+    assert_eq!(emulator.violations(), [Violation::DataBeforeInit]);
+    assert_eq!(Violation::DataBeforeInit.to_string(), "display data written before the display was initialized");
+    emulator.clear_violations();
+
+    // Turning the display on without initializing it is tolerated.
+    send(&mut emulator, DisplayMode::Reset, &[]);
+    send(&mut emulator, DisplayMode::Command, &[DISPLAYON]);
+    send(&mut emulator, DisplayMode::Data, &[0x9A]);
+    assert_eq!(emulator.violations(), []);
+  }
+
+  #[test]
+  fn test_horizontal_scroll() {
+    let mut emulator = Emulator::new();
+    send(&mut emulator, DisplayMode::Data, &[0x12, 0x34]);
+    send(&mut emulator, DisplayMode::Command, &[SETUPHSCROLL, 1, 0, 1, 0x00]);
+    emulator.step_horizontal_scroll(1);
+    assert_eq!(emulator.gddram()[0][0 .. 2], [0x12, 0x34]);
+
+    send(&mut emulator, DisplayMode::Command, &[ACTIVATESCROLL]);
+    emulator.step_horizontal_scroll(2);
+    assert_eq!(emulator.gddram()[0][0 .. 4], [0x00, 0x00, 0x12, 0x34]);
+  }
+
+}
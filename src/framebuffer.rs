@@ -0,0 +1,281 @@
+use unpack_line_for_display;
+use {HEIGHT, WIDTH};
+
+/// An in-memory image the size of the display, addressed one pixel at a time.
+///
+/// Coordinates are signed so that drawing may extend beyond the edges of the image;
+/// pixels outside of the image are ignored when written and `None` when read.
+pub trait FrameBuffer {
+  /// Largest value a pixel can hold. All other values are clamped to it.
+  fn max_value(&self) -> u8;
+
+  /// Sets the pixel at (`x`, `y`) to `value`, if it is within the image.
+  fn set_pixel(&mut self, x: i32, y: i32, value: u8);
+
+  /// Returns the value of the pixel at (`x`, `y`), or `None` if it is outside of the image.
+  fn get_pixel(&self, x: i32, y: i32) -> Option<u8>;
+
+  /// Sets every pixel to `value`.
+  fn fill(&mut self, value: u8);
+
+  /// Sets every pixel to `0`.
+  fn clear(&mut self) {
+    self.fill(0);
+  }
+
+  /// Replaces the value of every pixel with its complement relative to `max_value`.
+  fn invert(&mut self);
+}
+
+/// Returns the index of the pixel at (`x`, `y`) as `(x, y)`, if it is within the display.
+fn pixel_index(x: i32, y: i32) -> Option<(usize, usize)> {
+  if x >= 0 && y >= 0 && (x as usize) < WIDTH && (y as usize) < HEIGHT {
+    Some((x as usize, y as usize))
+  } else {
+    None
+  }
+}
+
+/// A 1-bit frame buffer in the format accepted by `Ssd1325::blit_l1`.
+/// Pixels are either `0` (off) or `1` (on).
+#[derive(Copy,Clone)]
+pub struct FrameBufferL1 {
+  /// Rows of pixels packed 8 per byte, most significant bit first.
+  frame: [[u8; 16]; 64],
+}
+
+impl FrameBufferL1 {
+
+  /// Returns a new frame buffer with all pixels off.
+  pub fn new() -> Self {
+    FrameBufferL1 { frame: [[0u8; 16]; 64] }
+  }
+
+  /// Returns a frame buffer holding a copy of a packed 1-bit `frame`.
+  pub fn from_frame(frame: &[[u8; 16]; 64]) -> Self {
+    FrameBufferL1 { frame: *frame }
+  }
+
+  /// Returns the image in the format accepted by `Ssd1325::blit_l1`.
+  pub fn as_frame(&self) -> &[[u8; 16]; 64] {
+    &self.frame
+  }
+
+  /// Returns the image in the format accepted by `Ssd1325::blit_l4`, with pixels that are on
+  /// at full brightness.
+  pub fn to_l4(&self) -> FrameBufferL4 {
+    let mut converted = FrameBufferL4::new();
+    for (line, converted_line) in self.frame.iter().zip(converted.frame.iter_mut()) {
+      unpack_line_for_display(line, converted_line);
+    }
+    converted
+  }
+
+}
+
+impl Default for FrameBufferL1 {
+  fn default() -> Self {
+    FrameBufferL1::new()
+  }
+}
+
+impl FrameBuffer for FrameBufferL1 {
+  fn max_value(&self) -> u8 {
+    1
+  }
+
+  fn set_pixel(&mut self, x: i32, y: i32, value: u8) {
+    if let Some((x, y)) = pixel_index(x, y) {
+      let mask = 0x80 >> (x % 8);
+      if value != 0 {
+        self.frame[y][x / 8] |= mask;
+      } else {
+        self.frame[y][x / 8] &= !mask;
+      }
+    }
+  }
+
+  fn get_pixel(&self, x: i32, y: i32) -> Option<u8> {
+    pixel_index(x, y).map(|(x, y)| (self.frame[y][x / 8] >> (7 - (x % 8))) & 0x01)
+  }
+
+  fn fill(&mut self, value: u8) {
+    let byte = if value != 0 { 0xFF } else { 0x00 };
+    self.frame = [[byte; 16]; 64];
+  }
+
+  fn invert(&mut self) {
+    for byte in self.frame.iter_mut().flat_map(|line| line.iter_mut()) {
+      *byte = !*byte;
+    }
+  }
+}
+
+/// A 4-bit grayscale frame buffer in the format accepted by `Ssd1325::blit_l4`.
+/// Pixels range from `0x0` (off) to `0xF` (full brightness).
+#[derive(Copy,Clone)]
+pub struct FrameBufferL4 {
+  /// Rows of pixels packed 2 per byte, most significant nibble first.
+  frame: [[u8; 64]; 64],
+}
+
+impl FrameBufferL4 {
+
+  /// Returns a new frame buffer with all pixels off.
+  pub fn new() -> Self {
+    FrameBufferL4 { frame: [[0u8; 64]; 64] }
+  }
+
+  /// Returns a frame buffer holding a copy of a packed 4-bit `frame`.
+  pub fn from_frame(frame: &[[u8; 64]; 64]) -> Self {
+    FrameBufferL4 { frame: *frame }
+  }
+
+  /// Returns the image in the format accepted by `Ssd1325::blit_l4`.
+  pub fn as_frame(&self) -> &[[u8; 64]; 64] {
+    &self.frame
+  }
+
+  /// Returns the image in the format accepted by `Ssd1325::blit_l1`, with pixels at or above
+  /// `threshold` on and all others off.
+  pub fn to_l1(&self, threshold: u8) -> FrameBufferL1 {
+    let mut converted = FrameBufferL1::new();
+    for y in 0 .. HEIGHT as i32 {
+      for x in 0 .. WIDTH as i32 {
+        if self.get_pixel(x, y).unwrap_or(0) >= threshold {
+          converted.set_pixel(x, y, 1);
+        }
+      }
+    }
+    converted
+  }
+
+}
+
+impl Default for FrameBufferL4 {
+  fn default() -> Self {
+    FrameBufferL4::new()
+  }
+}
+
+impl FrameBuffer for FrameBufferL4 {
+  fn max_value(&self) -> u8 {
+    0x0F
+  }
+
+  fn set_pixel(&mut self, x: i32, y: i32, value: u8) {
+    if let Some((x, y)) = pixel_index(x, y) {
+      let value = value.min(0x0F);
+      let byte = &mut self.frame[y][x / 2];
+      if x & 1 == 0 {
+        *byte = (*byte & 0x0F) | (value << 4);
+      } else {
+        *byte = (*byte & 0xF0) | value;
+      }
+    }
+  }
+
+  fn get_pixel(&self, x: i32, y: i32) -> Option<u8> {
+    pixel_index(x, y).map(|(x, y)| {
+      let byte = self.frame[y][x / 2];
+      if x & 1 == 0 { byte >> 4 } else { byte & 0x0F }
+    })
+  }
+
+  fn fill(&mut self, value: u8) {
+    let value = value.min(0x0F);
+    self.frame = [[(value << 4) | value; 64]; 64];
+  }
+
+  fn invert(&mut self) {
+    for byte in self.frame.iter_mut().flat_map(|line| line.iter_mut()) {
+      *byte = !*byte;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_l1_pixels() {
+    let mut buffer = FrameBufferL1::new();
+    buffer.set_pixel(0, 0, 1);
+    buffer.set_pixel(9, 1, 0xFF);
+    buffer.set_pixel(127, 63, 1);
+    assert_eq!(buffer.as_frame()[0][0], 0b1000_0000);
+    assert_eq!(buffer.as_frame()[1][1], 0b0100_0000);
+    assert_eq!(buffer.as_frame()[63][15], 0b0000_0001);
+    assert_eq!(buffer.get_pixel(9, 1), Some(1));
+    assert_eq!(buffer.get_pixel(10, 1), Some(0));
+
+    // Clearing a pixel leaves its neighbours intact.
+    buffer.set_pixel(9, 1, 0);
+    buffer.set_pixel(8, 1, 1);
+    assert_eq!(buffer.as_frame()[1][1], 0b1000_0000);
+
+    // Pixels outside of the image are ignored.
+    buffer.set_pixel(-1, 0, 1);
+    buffer.set_pixel(128, 0, 1);
+    buffer.set_pixel(0, 64, 1);
+    assert_eq!(buffer.get_pixel(-1, 0), None);
+    assert_eq!(buffer.get_pixel(0, 64), None);
+    assert_eq!(buffer.as_frame()[0][0], 0b1000_0000);
+  }
+
+  #[test]
+  fn test_l1_fill_and_invert() {
+    let mut buffer = FrameBufferL1::new();
+    buffer.fill(1);
+    assert_eq!(buffer.as_frame(), &[[0xFF; 16]; 64]);
+    buffer.set_pixel(0, 0, 0);
+    buffer.invert();
+    assert_eq!(buffer.get_pixel(0, 0), Some(1));
+    assert_eq!(buffer.get_pixel(1, 0), Some(0));
+    buffer.clear();
+    assert_eq!(buffer.as_frame(), &[[0x00; 16]; 64]);
+  }
+
+  #[test]
+  fn test_l4_pixels() {
+    let mut buffer = FrameBufferL4::new();
+    buffer.set_pixel(0, 0, 0xA);
+    buffer.set_pixel(1, 0, 0x5);
+    buffer.set_pixel(3, 2, 0xFF);
+    assert_eq!(buffer.as_frame()[0][0], 0xA5);
+    assert_eq!(buffer.as_frame()[2][1], 0x0F);
+    assert_eq!(buffer.get_pixel(0, 0), Some(0xA));
+    assert_eq!(buffer.get_pixel(1, 0), Some(0x5));
+    assert_eq!(buffer.get_pixel(3, 2), Some(0xF));
+    assert_eq!(buffer.get_pixel(128, 0), None);
+  }
+
+  #[test]
+  fn test_l4_fill_and_invert() {
+    let mut buffer = FrameBufferL4::new();
+    buffer.fill(0x3);
+    assert_eq!(buffer.as_frame(), &[[0x33; 64]; 64]);
+    buffer.set_pixel(0, 0, 0x0);
+    buffer.invert();
+    assert_eq!(buffer.get_pixel(0, 0), Some(0xF));
+    assert_eq!(buffer.get_pixel(1, 0), Some(0xC));
+  }
+
+  #[test]
+  fn test_conversions() {
+    let mut buffer = FrameBufferL1::new();
+    buffer.set_pixel(0, 0, 1);
+    buffer.set_pixel(3, 0, 1);
+    let gray = buffer.to_l4();
+    assert_eq!(&gray.as_frame()[0][0..2], [0xF0, 0x0F]);
+
+    let mut gray = FrameBufferL4::new();
+    gray.set_pixel(0, 0, 0x7);
+    gray.set_pixel(1, 0, 0x8);
+    let mono = gray.to_l1(0x8);
+    assert_eq!(mono.get_pixel(0, 0), Some(0));
+    assert_eq!(mono.get_pixel(1, 0), Some(1));
+  }
+
+}
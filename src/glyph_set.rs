@@ -0,0 +1,429 @@
+use std::collections::BTreeMap;
+use std::{error, fs, path};
+
+use text::{Font, Glyph};
+use DisplayError;
+
+/// Largest width or height of a BDF glyph, far beyond any font legible on the display.
+const BDF_MAX_GLYPH_SIZE: i32 = 255;
+
+/// Magic number at the start of PSF version 1 fonts.
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+/// PSF version 1 mode bit selecting 512 glyphs instead of 256.
+const PSF1_MODE512: u8 = 0x01;
+/// PSF version 1 mode bits indicating that a Unicode table follows the glyphs.
+const PSF1_MODEHASTAB: u8 = 0x02 | 0x04;
+/// PSF version 1 Unicode table entry separating a glyph's sequences from its code points.
+const PSF1_STARTSEQ: u16 = 0xFFFE;
+/// PSF version 1 Unicode table entry terminating the entries of a glyph.
+const PSF1_SEPARATOR: u16 = 0xFFFF;
+
+/// Magic number at the start of PSF version 2 fonts.
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+/// PSF version 2 flag indicating that a Unicode table follows the glyphs.
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+/// PSF version 2 Unicode table byte separating a glyph's sequences from its code points.
+const PSF2_STARTSEQ: u8 = 0xFE;
+/// PSF version 2 Unicode table byte terminating the entries of a glyph.
+const PSF2_SEPARATOR: u8 = 0xFF;
+
+/// A glyph owned by a `GlyphSet`.
+#[derive(Clone,Debug,Eq,PartialEq)]
+struct StoredGlyph {
+  width: i32,
+  height: i32,
+  x_offset: i32,
+  y_offset: i32,
+  advance: i32,
+  bitmap: Vec<u8>,
+}
+
+/// A bitmap font loaded at runtime from a BDF or PSF font file, mapping Unicode characters to
+/// glyphs. Draw text with it using `Canvas::draw_text`.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct GlyphSet {
+  line_height: i32,
+  glyphs: Vec<StoredGlyph>,
+  characters: BTreeMap<char, usize>,
+}
+
+impl GlyphSet {
+
+  /// Loads the font file at `path`, detecting whether it is a BDF or PSF font from its contents.
+  pub fn open<P: AsRef<path::Path>>(path: P) -> Result<GlyphSet, Box<dyn error::Error>> {
+    let data = fs::read(path)?;
+    if data.starts_with(&PSF1_MAGIC) || data.starts_with(&PSF2_MAGIC) {
+      Ok(GlyphSet::from_psf(&data)?)
+    } else {
+      Ok(GlyphSet::from_bdf(&data)?)
+    }
+  }
+
+  /// Parses a font in the Glyph Bitmap Distribution Format (BDF).
+  ///
+  /// Glyph encodings are taken to be Unicode code points, as they are for fonts in the
+  /// `ISO10646-1` and `ISO8859-1` character sets. Glyphs without an encoding are ignored.
+  /// Lines are as tall as the font ascent and descent, with glyphs positioned on the baseline
+  /// and advancing by their own width, so proportional fonts are supported.
+  ///
+  /// # Returns
+  /// `DisplayError::InvalidFont` if the font is malformed.
+  pub fn from_bdf(data: &[u8]) -> Result<GlyphSet, DisplayError> {
+    let source = String::from_utf8_lossy(data);
+    let mut lines = source.lines().map(|line| line.trim());
+    if !lines.next().is_some_and(|line| line.starts_with("STARTFONT")) {
+      return Err(DisplayError::InvalidFont);
+    }
+
+    let mut bounding_box: Option<[i32; 4]> = None;
+    let mut ascent: Option<i32> = None;
+    let mut descent: Option<i32> = None;
+    let mut placed: Vec<(char, StoredGlyph, i32)> = Vec::new();
+
+    while let Some(line) = lines.next() {
+      let mut fields = line.split_whitespace();
+      match fields.next() {
+        Some("FONTBOUNDINGBOX") => bounding_box = Some(parse_integers(fields)?),
+        Some("FONT_ASCENT") => ascent = Some(parse_integers::<_, [i32; 1]>(fields)?[0]),
+        Some("FONT_DESCENT") => descent = Some(parse_integers::<_, [i32; 1]>(fields)?[0]),
+        Some("STARTCHAR") => {
+          let mut encoding: Option<i32> = None;
+          let mut advance: Option<i32> = None;
+          let mut glyph_box: Option<[i32; 4]> = None;
+          loop {
+            let mut fields = lines.next().ok_or(DisplayError::InvalidFont)?.split_whitespace();
+            match fields.next() {
+              Some("ENCODING") => encoding = Some(parse_integers::<_, [i32; 1]>(fields)?[0]),
+              Some("DWIDTH") => advance = Some(parse_integers::<_, [i32; 1]>(fields)?[0]),
+              Some("BBX") => glyph_box = Some(parse_integers(fields)?),
+              Some("BITMAP") => break,
+              Some("ENDCHAR") => return Err(DisplayError::InvalidFont),
+              _ => {}
+            }
+          }
+
+          let [width, height, x_offset, y_offset] = glyph_box.or(bounding_box).ok_or(DisplayError::InvalidFont)?;
+          if width < 0 || height < 0 || width > BDF_MAX_GLYPH_SIZE || height > BDF_MAX_GLYPH_SIZE {
+            return Err(DisplayError::InvalidFont);
+          }
+          let stride = (width as usize).div_ceil(8);
+          let mut bitmap = Vec::with_capacity(stride * height as usize);
+          for _ in 0 .. height {
+            let row = lines.next().ok_or(DisplayError::InvalidFont)?;
+            for index in 0 .. stride {
+              let byte = row.get(index * 2 .. index * 2 + 2).unwrap_or("00");
+              bitmap.push(u8::from_str_radix(byte, 16).map_err(|_| DisplayError::InvalidFont)?);
+            }
+          }
+          if lines.next() != Some("ENDCHAR") {
+            return Err(DisplayError::InvalidFont);
+          }
+
+          // The vertical offset is relative to the baseline until the ascent is known.
+          let character = encoding
+            .filter(|&encoding| encoding >= 0)
+            .and_then(|encoding| ::std::char::from_u32(encoding as u32));
+          if let Some(character) = character {
+            let glyph = StoredGlyph { width, height, x_offset, y_offset: 0, advance: advance.unwrap_or(width), bitmap };
+            placed.push((character, glyph, y_offset + height));
+          }
+        }
+        _ => {}
+      }
+    }
+
+    let bounding_box = bounding_box.ok_or(DisplayError::InvalidFont)?;
+    let ascent = ascent.unwrap_or(bounding_box[1] + bounding_box[3]);
+    let descent = descent.unwrap_or(-bounding_box[3]);
+
+    let mut set = GlyphSet { line_height: ascent + descent, glyphs: Vec::new(), characters: BTreeMap::new() };
+    for (character, mut glyph, top) in placed {
+      glyph.y_offset = ascent - top;
+      set.characters.insert(character, set.glyphs.len());
+      set.glyphs.push(glyph);
+    }
+    Ok(set)
+  }
+
+  /// Parses a Linux console font in the PC Screen Font format, version 1 or 2 (PSF).
+  ///
+  /// Characters are mapped to glyphs using the Unicode table of the font. Without one, glyph
+  /// `n` is used for code point `n`. Sequences of code points in the table are ignored.
+  ///
+  /// # Returns
+  /// `DisplayError::InvalidFont` if the font is malformed.
+  pub fn from_psf(data: &[u8]) -> Result<GlyphSet, DisplayError> {
+    let (width, height, count, glyph_size, glyphs_start, has_table, is_version_1) =
+      if data.starts_with(&PSF1_MAGIC) && data.len() >= 4 {
+        let count = if (data[2] & PSF1_MODE512) != 0 { 512 } else { 256 };
+        let height = data[3] as usize;
+        (8, height, count, height, 4, (data[2] & PSF1_MODEHASTAB) != 0, true)
+      } else if data.starts_with(&PSF2_MAGIC) && data.len() >= 32 {
+        let field = |index: usize| {
+          let bytes = [data[index * 4], data[index * 4 + 1], data[index * 4 + 2], data[index * 4 + 3]];
+          u32::from_le_bytes(bytes) as usize
+        };
+        let (header_size, flags, count, glyph_size, height, width) = (field(2), field(3), field(4), field(5), field(6), field(7));
+        (width, height, count, glyph_size, header_size, (flags as u32 & PSF2_HAS_UNICODE_TABLE) != 0, false)
+      } else {
+        return Err(DisplayError::InvalidFont);
+      };
+
+    let stride = width.div_ceil(8);
+    if width == 0 || height == 0 || glyph_size < stride * height || count == 0 {
+      return Err(DisplayError::InvalidFont);
+    }
+    let glyphs_end = count.checked_mul(glyph_size)
+      .and_then(|size| size.checked_add(glyphs_start))
+      .filter(|&end| end <= data.len())
+      .ok_or(DisplayError::InvalidFont)?;
+
+    let mut set = GlyphSet { line_height: height as i32, glyphs: Vec::with_capacity(count), characters: BTreeMap::new() };
+    for glyph in data[glyphs_start .. glyphs_end].chunks(glyph_size) {
+      set.glyphs.push(StoredGlyph {
+        width: width as i32,
+        height: height as i32,
+        x_offset: 0,
+        y_offset: 0,
+        advance: width as i32,
+        bitmap: glyph[.. stride * height].to_vec(),
+      });
+    }
+
+    let table = &data[glyphs_end ..];
+    if !has_table {
+      for index in 0 .. count {
+        if let Some(character) = ::std::char::from_u32(index as u32) {
+          set.characters.insert(character, index);
+        }
+      }
+    } else if is_version_1 {
+      let mut entries = table.chunks(2).filter(|pair| pair.len() == 2).map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+      for index in 0 .. count {
+        let mut in_sequence = false;
+        for entry in entries.by_ref() {
+          match entry {
+            PSF1_SEPARATOR => break,
+            PSF1_STARTSEQ => in_sequence = true,
+            _ if !in_sequence => {
+              if let Some(character) = ::std::char::from_u32(u32::from(entry)) {
+                set.characters.entry(character).or_insert(index);
+              }
+            }
+            _ => {}
+          }
+        }
+      }
+    } else {
+      let mut entries = table.split(|&byte| byte == PSF2_SEPARATOR);
+      for index in 0 .. count {
+        let entry = entries.next().ok_or(DisplayError::InvalidFont)?;
+        let code_points = entry.split(|&byte| byte == PSF2_STARTSEQ).next().unwrap_or(&[]);
+        let code_points = ::std::str::from_utf8(code_points).map_err(|_| DisplayError::InvalidFont)?;
+        for character in code_points.chars() {
+          set.characters.entry(character).or_insert(index);
+        }
+      }
+    }
+
+    Ok(set)
+  }
+
+  /// Returns the number of characters mapped to glyphs.
+  pub fn len(&self) -> usize {
+    self.characters.len()
+  }
+
+  /// Returns whether no characters are mapped to glyphs.
+  pub fn is_empty(&self) -> bool {
+    self.characters.is_empty()
+  }
+
+  /// Returns whether the font includes a glyph for `character`.
+  pub fn contains(&self, character: char) -> bool {
+    self.characters.contains_key(&character)
+  }
+
+}
+
+impl Font for GlyphSet {
+  fn line_height(&self) -> i32 {
+    self.line_height
+  }
+
+  fn glyph(&self, character: char) -> Option<Glyph<'_>> {
+    self.characters.get(&character).map(|&index| {
+      let glyph = &self.glyphs[index];
+      Glyph {
+        width: glyph.width,
+        height: glyph.height,
+        x_offset: glyph.x_offset,
+        y_offset: glyph.y_offset,
+        advance: glyph.advance,
+        bitmap: &glyph.bitmap,
+      }
+    })
+  }
+}
+
+/// Parses the whitespace-separated integers of a BDF property into the array `A`.
+fn parse_integers<'a, I: Iterator<Item = &'a str>, A: Default + AsMut<[i32]>>(mut fields: I) -> Result<A, DisplayError> {
+  let mut values = A::default();
+  for value in values.as_mut().iter_mut() {
+    *value = fields.next()
+      .and_then(|field| field.parse().ok())
+      .ok_or(DisplayError::InvalidFont)?;
+  }
+  Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use draw::Canvas;
+  use framebuffer::{FrameBuffer, FrameBufferL1};
+  use text::{text_width, TextStyle};
+
+  /// A proportional font with a narrow `i`, a wide `m` and a `g` with a descender.
+  const BDF: &str = "STARTFONT 2.1
+FONT -Test-Proportional-Medium-R-Normal--8-80-75-75-P-50-ISO10646-1
+SIZE 8 75 75
+FONTBOUNDINGBOX 5 8 0 -2
+STARTPROPERTIES 2
+FONT_ASCENT 6
+FONT_DESCENT 2
+ENDPROPERTIES
+CHARS 4
+STARTCHAR i
+ENCODING 105
+DWIDTH 2 0
+BBX 1 5 0 0
+BITMAP
+80
+00
+80
+80
+80
+ENDCHAR
+STARTCHAR m
+ENCODING 109
+DWIDTH 6 0
+BBX 5 3 0 0
+BITMAP
+D0
+A8
+A8
+ENDCHAR
+STARTCHAR g
+ENCODING 103
+DWIDTH 4 0
+BBX 3 5 0 -2
+BITMAP
+E0
+A0
+E0
+20
+E0
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+DWIDTH 4 0
+BBX 1 1 0 0
+BITMAP
+80
+ENDCHAR
+ENDFONT
+";
+
+  #[test]
+  fn test_bdf() {
+    let font = GlyphSet::from_bdf(BDF.as_bytes()).unwrap();
+    assert_eq!(font.len(), 3);
+    assert_eq!(font.line_height(), 8);
+    assert!(font.contains('i') && !font.contains('x'));
+
+    let m = font.glyph('m').unwrap();
+    assert_eq!((m.width, m.height, m.x_offset, m.y_offset, m.advance), (5, 3, 0, 3, 6));
+    assert_eq!(m.bitmap, &[0xD0, 0xA8, 0xA8]);
+
+    // Descenders extend below the baseline, which is 6 rows from the top of the line.
+    let g = font.glyph('g').unwrap();
+    assert_eq!((g.y_offset, g.height), (3, 5));
+
+    // Proportional advances are used for measurement and drawing.
+    assert_eq!(text_width(&font, "mig"), 12);
+    let mut buffer = FrameBufferL1::new();
+    buffer.draw_text(&font, "im", 0, 0, &TextStyle::new(1));
+    assert_eq!(buffer.get_pixel(0, 1), Some(1));
+    assert_eq!(buffer.get_pixel(2, 3), Some(1));
+    assert_eq!(buffer.get_pixel(2, 2), Some(0));
+  }
+
+  #[test]
+  fn test_invalid_bdf() {
+    assert_eq!(GlyphSet::from_bdf(b"not a font"), Err(DisplayError::InvalidFont));
+    let truncated = &BDF[.. BDF.find("A8").unwrap()];
+    assert_eq!(GlyphSet::from_bdf(truncated.as_bytes()), Err(DisplayError::InvalidFont));
+    let corrupt = BDF.replace("D0", "ZZ");
+    assert_eq!(GlyphSet::from_bdf(corrupt.as_bytes()), Err(DisplayError::InvalidFont));
+    let oversized = BDF.replace("BBX 1 5 0 0", "BBX 2147483647 2147483647 0 0");
+    assert_eq!(GlyphSet::from_bdf(oversized.as_bytes()), Err(DisplayError::InvalidFont));
+  }
+
+  #[test]
+  fn test_psf1() {
+    // A 256 glyph font, 4 rows tall, where glyph 1 is mapped to 'A' and 'Ä'.
+    let mut data = vec![0x36, 0x04, PSF1_MODEHASTAB, 4];
+    for index in 0 .. 256 {
+      data.extend_from_slice(&[index as u8, 0xFF, 0x00, 0x81]);
+    }
+    for index in 0u16 .. 256 {
+      match index {
+        1 => data.extend_from_slice(&[0x41, 0x00, 0xC4, 0x00]),
+        2 => data.extend_from_slice(&[0x42, 0x00, 0xFE, 0xFF, 0x41, 0x00, 0x08, 0x03]),
+        _ => {}
+      }
+      data.extend_from_slice(&[0xFF, 0xFF]);
+    }
+
+    let font = GlyphSet::from_psf(&data).unwrap();
+    assert_eq!(font.len(), 3);
+    assert_eq!(font.line_height(), 4);
+    assert_eq!(font.glyph('A').unwrap().bitmap, &[0x01, 0xFF, 0x00, 0x81]);
+    assert_eq!(font.glyph('Ä').unwrap().bitmap, &[0x01, 0xFF, 0x00, 0x81]);
+    assert_eq!(font.glyph('B').unwrap().bitmap[0], 0x02);
+    assert_eq!(font.glyph('a'), None);
+
+    // Without a table, glyphs map to the code points of their index.
+    data[2] = 0;
+    let font = GlyphSet::from_psf(&data).unwrap();
+    assert_eq!(font.len(), 256);
+    assert_eq!(font.glyph('A').unwrap().bitmap[0], 0x41);
+  }
+
+  #[test]
+  fn test_psf2() {
+    // Two glyphs, 10 pixels wide and 2 rows tall, mapped to 'x' and '€'.
+    let mut data = PSF2_MAGIC.to_vec();
+    for &field in [0u32, 32, PSF2_HAS_UNICODE_TABLE, 2, 4, 2, 10].iter() {
+      data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(&[0xFF, 0xC0, 0x80, 0x40]);
+    data.extend_from_slice(&[0xAA, 0x80, 0x55, 0x40]);
+    data.extend_from_slice(b"x\xFF");
+    data.extend_from_slice("€".as_bytes());
+    data.push(PSF2_SEPARATOR);
+
+    let font = GlyphSet::from_psf(&data).unwrap();
+    assert_eq!(font.len(), 2);
+    let glyph = font.glyph('€').unwrap();
+    assert_eq!((glyph.width, glyph.height, glyph.advance), (10, 2, 10));
+    assert!(glyph.pixel(8, 0) && !glyph.pixel(9, 0) && glyph.pixel(9, 1));
+    assert!(font.glyph('x').unwrap().pixel(9, 0));
+
+    // Truncated glyph data is rejected.
+    assert_eq!(GlyphSet::from_psf(&data[.. 36]), Err(DisplayError::InvalidFont));
+    assert_eq!(GlyphSet::from_psf(&[0x36, 0x04]), Err(DisplayError::InvalidFont));
+  }
+
+}
@@ -0,0 +1,105 @@
+use std::convert::Infallible;
+
+use embedded_graphics::geometry::{OriginDimensions, Size};
+use embedded_graphics::pixelcolor::{BinaryColor, Gray4, GrayColor};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::Pixel;
+
+use framebuffer::{FrameBuffer, FrameBufferL1, FrameBufferL4};
+use {HEIGHT, WIDTH};
+
+impl OriginDimensions for FrameBufferL1 {
+  fn size(&self) -> Size {
+    Size::new(WIDTH as u32, HEIGHT as u32)
+  }
+}
+
+/// Draws `BinaryColor` pixels with `embedded-graphics`. Send the frame buffer to the display
+/// with `Ssd1325::blit_l1(buffer.as_frame())`.
+impl DrawTarget for FrameBufferL1 {
+  type Color = BinaryColor;
+  type Error = Infallible;
+
+  fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where I: IntoIterator<Item = Pixel<Self::Color>>
+  {
+    for Pixel(point, color) in pixels {
+      self.set_pixel(point.x, point.y, color.is_on() as u8);
+    }
+    Ok(())
+  }
+
+  fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+    self.fill(color.is_on() as u8);
+    Ok(())
+  }
+}
+
+impl OriginDimensions for FrameBufferL4 {
+  fn size(&self) -> Size {
+    Size::new(WIDTH as u32, HEIGHT as u32)
+  }
+}
+
+/// Draws `Gray4` pixels with `embedded-graphics`. Send the frame buffer to the display
+/// with `Ssd1325::blit_l4(buffer.as_frame())`.
+impl DrawTarget for FrameBufferL4 {
+  type Color = Gray4;
+  type Error = Infallible;
+
+  fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where I: IntoIterator<Item = Pixel<Self::Color>>
+  {
+    for Pixel(point, color) in pixels {
+      self.set_pixel(point.x, point.y, color.luma());
+    }
+    Ok(())
+  }
+
+  fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+    self.fill(color.luma());
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use embedded_graphics::prelude::*;
+  use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
+
+  #[test]
+  fn test_binary_color() {
+    let mut buffer = FrameBufferL1::new();
+    Line::new(Point::new(-4, 0), Point::new(7, 0))
+      .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+      .draw(&mut buffer)
+      .unwrap();
+    assert_eq!(buffer.as_frame()[0][0], 0xFF);
+    assert_eq!(buffer.as_frame()[0][1], 0x00);
+
+    DrawTarget::clear(&mut buffer, BinaryColor::On).unwrap();
+    assert_eq!(buffer.as_frame(), &[[0xFF; 16]; 64]);
+  }
+
+  #[test]
+  fn test_gray4() {
+    let mut buffer = FrameBufferL4::new();
+    assert_eq!(buffer.bounding_box(), Rectangle::new(Point::zero(), Size::new(128, 64)));
+
+    Rectangle::new(Point::new(1, 1), Size::new(2, 1))
+      .into_styled(PrimitiveStyle::with_fill(Gray4::new(0xA)))
+      .draw(&mut buffer)
+      .unwrap();
+    assert_eq!(buffer.as_frame()[1][0], 0x0A);
+    assert_eq!(buffer.as_frame()[1][1], 0xA0);
+
+    // Pixels outside of the image are ignored.
+    Pixel(Point::new(128, 0), Gray4::WHITE).draw(&mut buffer).unwrap();
+    assert_eq!(buffer.as_frame()[0], [0x00; 64]);
+
+    DrawTarget::clear(&mut buffer, Gray4::new(0x3)).unwrap();
+    assert_eq!(buffer.as_frame(), &[[0x33; 64]; 64]);
+  }
+
+}
@@ -0,0 +1,172 @@
+use DisplayError;
+
+/// Largest pulse width increment which can be encoded in the gray scale table.
+const MAX_INCREMENT: u8 = 0x7;
+
+/// Gray scale tables for common response curves, to be sent with `Ssd1325::set_gray_table`.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum GrayTablePreset {
+  /// Table used by the Adafruit reference driver. Used by the default configuration.
+  Adafruit,
+  /// Pulse width proportional to gray level, using the full range of the display.
+  Linear,
+  /// Pulse width following a gamma curve of 1.8.
+  Gamma18,
+  /// Pulse width following a gamma curve of 2.2, approximating sRGB.
+  Gamma22,
+  /// Pulse width following a gamma curve of the given exponent.
+  Gamma(f64),
+}
+
+impl GrayTablePreset {
+  /// Returns the gray scale table for the preset.
+  pub fn table(&self) -> [u8; 8] {
+    match *self {
+      GrayTablePreset::Adafruit => [0x01, 0x11, 0x22, 0x32, 0x43, 0x54, 0x65, 0x76],
+      GrayTablePreset::Linear => gray_table_for_gamma(1.0),
+      GrayTablePreset::Gamma18 => gray_table_for_gamma(1.8),
+      GrayTablePreset::Gamma22 => gray_table_for_gamma(2.2),
+      GrayTablePreset::Gamma(gamma) => gray_table_for_gamma(gamma),
+    }
+  }
+}
+
+/// Checks that each entry of a gray scale `table` is a pair of 3-bit pulse width increments.
+///
+/// The table holds 16 nibbles, the low nibble of each byte first. Nibble `n` is the increment
+/// in pulse width of gray level `n + 1` over gray level `n`, from `0` to `7`. Level `0` is
+/// always off and the final nibble is unused.
+pub fn validate_gray_table(table: &[u8; 8]) -> Result<(), DisplayError> {
+  if table.iter().all(|&entry| (entry & 0x88) == 0) {
+    Ok(())
+  } else {
+    Err(DisplayError::ArgumentOutOfRange)
+  }
+}
+
+/// Returns the gray scale table whose pulse widths follow `(level / 15) ^ gamma`, scaled to
+/// the largest range representable in the table. Non-positive or non-finite exponents are
+/// treated as `1.0`.
+pub fn gray_table_for_gamma(gamma: f64) -> [u8; 8] {
+  let gamma = if gamma.is_finite() && gamma > 0.0 { gamma } else { 1.0 };
+  let mut targets = [0f64; 15];
+  for (index, target) in targets.iter_mut().enumerate() {
+    *target = ((index + 1) as f64 / 15.0).powf(gamma);
+  }
+  gray_table_for_luminance(&targets).unwrap_or_else(|_| gray_table_for_gamma(1.0))
+}
+
+/// Returns the gray scale table producing the relative luminance `targets` for gray levels
+/// `1` to `15`, assuming luminance is proportional to pulse width. Targets must be between
+/// `0.0` and `1.0` and must not decrease; they are scaled to the largest range representable
+/// in the table, so only their relative values matter.
+///
+/// # Returns
+/// `DisplayError::ArgumentOutOfRange` if the targets are out of range, decreasing, or all zero.
+pub fn gray_table_for_luminance(targets: &[f64; 15]) -> Result<[u8; 8], DisplayError> {
+  let mut previous = 0.0;
+  let mut largest_step = 0f64;
+  for &target in targets.iter() {
+    if !(previous ..= 1.0).contains(&target) {
+      return Err(DisplayError::ArgumentOutOfRange);
+    }
+    largest_step = largest_step.max(target - previous);
+    previous = target;
+  }
+  if largest_step <= 0.0 {
+    return Err(DisplayError::ArgumentOutOfRange);
+  }
+
+  // Scale so that the steepest step uses the largest increment, carrying rounding errors
+  // forward so that the cumulative pulse width tracks the target.
+  let scale = f64::from(MAX_INCREMENT) / largest_step;
+  let mut increments = [0u8; 16];
+  let mut width = 0i32;
+  for (increment, &target) in increments.iter_mut().zip(targets.iter()) {
+    let step = ((target * scale).round() as i32 - width).clamp(0, i32::from(MAX_INCREMENT));
+    *increment = step as u8;
+    width += step;
+  }
+
+  let mut table = [0u8; 8];
+  for (entry, pair) in table.iter_mut().zip(increments.chunks(2)) {
+    *entry = (pair[1] << 4) | pair[0];
+  }
+  Ok(table)
+}
+
+/// Returns the pulse width of each of the 16 gray levels produced by a gray scale `table`,
+/// proportional to the luminance of each level.
+pub fn gray_table_pulse_widths(table: &[u8; 8]) -> [u8; 16] {
+  let mut widths = [0u8; 16];
+  let mut width = 0u8;
+  for level in 1 .. 16 {
+    let entry = table[(level - 1) / 2];
+    let increment = if (level - 1) & 1 == 0 { entry & 0x07 } else { (entry >> 4) & 0x07 };
+    width += increment;
+    widths[level] = width;
+  }
+  widths
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_validate_gray_table() {
+    assert_eq!(validate_gray_table(&GrayTablePreset::Adafruit.table()), Ok(()));
+    assert_eq!(validate_gray_table(&[0x77; 8]), Ok(()));
+    assert_eq!(validate_gray_table(&[0x08, 0, 0, 0, 0, 0, 0, 0]), Err(DisplayError::ArgumentOutOfRange));
+    assert_eq!(validate_gray_table(&[0, 0, 0, 0, 0, 0, 0, 0x80]), Err(DisplayError::ArgumentOutOfRange));
+  }
+
+  #[test]
+  fn test_pulse_widths() {
+    let widths = gray_table_pulse_widths(&GrayTablePreset::Adafruit.table());
+    assert_eq!(widths, [0, 1, 1, 2, 3, 5, 7, 9, 12, 15, 19, 23, 28, 33, 39, 45]);
+  }
+
+  #[test]
+  fn test_linear_table() {
+    let table = GrayTablePreset::Linear.table();
+    assert_eq!(table, [0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x07]);
+    let widths = gray_table_pulse_widths(&table);
+    for (level, &width) in widths.iter().enumerate() {
+      assert_eq!(width as usize, level * 7);
+    }
+  }
+
+  #[test]
+  fn test_gamma_tables() {
+    for &preset in [GrayTablePreset::Gamma18, GrayTablePreset::Gamma22, GrayTablePreset::Gamma(0.5)].iter() {
+      let table = preset.table();
+      assert_eq!(validate_gray_table(&table), Ok(()));
+
+      // Pulse widths must increase with gray level.
+      let widths = gray_table_pulse_widths(&table);
+      assert!(widths.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", preset);
+      assert!(widths[15] > widths[8]);
+    }
+
+    // Steeper curves keep the dark levels darker.
+    let gamma18 = gray_table_pulse_widths(&GrayTablePreset::Gamma18.table());
+    let gamma22 = gray_table_pulse_widths(&GrayTablePreset::Gamma22.table());
+    assert!(f64::from(gamma22[4]) / f64::from(gamma22[15]) < f64::from(gamma18[4]) / f64::from(gamma18[15]));
+
+    // Invalid exponents fall back to a linear response.
+    assert_eq!(gray_table_for_gamma(-1.0), GrayTablePreset::Linear.table());
+  }
+
+  #[test]
+  fn test_luminance_validation() {
+    let mut targets = [0.5f64; 15];
+    targets[14] = 1.0;
+    assert!(gray_table_for_luminance(&targets).is_ok());
+    targets[3] = 0.4;
+    assert_eq!(gray_table_for_luminance(&targets), Err(DisplayError::ArgumentOutOfRange));
+    assert_eq!(gray_table_for_luminance(&[0.0; 15]), Err(DisplayError::ArgumentOutOfRange));
+    assert_eq!(gray_table_for_luminance(&[1.5; 15]), Err(DisplayError::ArgumentOutOfRange));
+  }
+
+}
@@ -0,0 +1,507 @@
+use std::{error, fs, path};
+
+use quantize::{quantize_l8, Quantization};
+use {DisplayError, HEIGHT, WIDTH};
+
+/// Size of the BMP file header preceding the information header.
+const BMP_FILE_HEADER_SIZE: usize = 14;
+/// Size of the smallest supported BMP information header, `BITMAPINFOHEADER`.
+const BMP_INFO_HEADER_SIZE: usize = 40;
+/// BMP compression method for uncompressed pixels.
+const BMP_COMPRESSION_NONE: u32 = 0;
+
+/// How an image is placed on the display when converted to a frame.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum Placement {
+  /// Scale the image preserving its aspect ratio so that all of it is shown, centered on the
+  /// display with black bars along the remaining edges.
+  Fit,
+  /// Scale the image preserving its aspect ratio so that it covers the display, centered with
+  /// the edges beyond the display cropped.
+  Crop,
+  /// Show the image at its original size, centered on the display. Larger images are cropped
+  /// and smaller ones surrounded with black.
+  Center,
+}
+
+/// An 8-bit grayscale image of any size, loaded from a PBM, PGM or BMP file.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Image {
+  width: usize,
+  height: usize,
+  pixels: Vec<u8>,
+}
+
+impl Image {
+
+  /// Returns an image of `width` by `height` pixels from their 8-bit luminance, one byte per
+  /// pixel row by row.
+  ///
+  /// # Returns
+  /// `DisplayError::BufferSizeMismatch` if `pixels` does not hold `width * height` bytes.
+  pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Result<Image, DisplayError> {
+    if width.checked_mul(height) != Some(pixels.len()) {
+      return Err(DisplayError::BufferSizeMismatch);
+    }
+    Ok(Image { width, height, pixels })
+  }
+
+  /// Loads the image file at `path`, as decoded by `Image::decode`.
+  pub fn open<P: AsRef<path::Path>>(path: P) -> Result<Image, Box<dyn error::Error>> {
+    let data = fs::read(path)?;
+    Ok(Image::decode(&data)?)
+  }
+
+  /// Decodes a PBM, PGM or BMP image, detecting the format from its contents.
+  ///
+  /// # Returns
+  /// `DisplayError::InvalidImage` if the image is malformed or in an unsupported format.
+  pub fn decode(data: &[u8]) -> Result<Image, DisplayError> {
+    if data.starts_with(b"BM") {
+      Image::from_bmp(data)
+    } else {
+      Image::from_netpbm(data)
+    }
+  }
+
+  /// Decodes a PBM (`P1` and `P4`) or PGM (`P2` and `P5`) image. Black PBM pixels are off,
+  /// and PGM samples are scaled from their maximum value to `255`.
+  ///
+  /// # Returns
+  /// `DisplayError::InvalidImage` if the image is malformed or in an unsupported format.
+  pub fn from_netpbm(data: &[u8]) -> Result<Image, DisplayError> {
+    let mut reader = NetpbmReader { data, position: 0 };
+    let magic = reader.token()?;
+    let width = reader.integer()?;
+    let height = reader.integer()?;
+    let maximum = match magic {
+      b"P1" | b"P4" => 1,
+      b"P2" | b"P5" => reader.integer()?,
+      _ => return Err(DisplayError::InvalidImage),
+    };
+    let count = width.checked_mul(height).ok_or(DisplayError::InvalidImage)?;
+    if width == 0 || height == 0 || maximum == 0 || maximum > 0xFFFF {
+      return Err(DisplayError::InvalidImage);
+    }
+
+    // Each pixel takes at least a byte of plain samples, a bit of PBM raster or a sample of
+    // PGM raster, so images larger than the input are rejected before pixels are allocated.
+    let minimum_size = match magic {
+      b"P1" | b"P2" => Some(count),
+      b"P4" => width.div_ceil(8).checked_mul(height),
+      _ => count.checked_mul(if maximum > 0xFF { 2 } else { 1 }),
+    };
+    if minimum_size.is_none_or(|size| size > data.len() - reader.position) {
+      return Err(DisplayError::InvalidImage);
+    }
+
+    let mut pixels = Vec::with_capacity(count);
+    match magic {
+      b"P1" => {
+        while pixels.len() < count {
+          match reader.bit()? {
+            0 => pixels.push(0xFF),
+            _ => pixels.push(0x00),
+          }
+        }
+      }
+      b"P2" => {
+        for _ in 0 .. count {
+          pixels.push(scale_sample(reader.integer()?, maximum)?);
+        }
+      }
+      b"P4" => {
+        let stride = width.div_ceil(8);
+        let raster = reader.raster(stride * height)?;
+        for row in raster.chunks(stride) {
+          for x in 0 .. width {
+            let set = (row[x / 8] & (0x80 >> (x % 8))) != 0;
+            pixels.push(if set { 0x00 } else { 0xFF });
+          }
+        }
+      }
+      _ => {
+        let sample_size = if maximum > 0xFF { 2 } else { 1 };
+        let raster = reader.raster(count * sample_size)?;
+        for sample in raster.chunks(sample_size) {
+          let value = sample.iter().fold(0, |value, &byte| (value << 8) | byte as usize);
+          pixels.push(scale_sample(value, maximum)?);
+        }
+      }
+    }
+
+    Ok(Image { width, height, pixels })
+  }
+
+  /// Decodes an uncompressed BMP image with 1, 4 or 8 bits per pixel. Palette colors are
+  /// converted to their luminance.
+  ///
+  /// # Returns
+  /// `DisplayError::InvalidImage` if the image is malformed or in an unsupported format.
+  pub fn from_bmp(data: &[u8]) -> Result<Image, DisplayError> {
+    if !data.starts_with(b"BM") || data.len() < BMP_FILE_HEADER_SIZE + BMP_INFO_HEADER_SIZE {
+      return Err(DisplayError::InvalidImage);
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let u32_at = |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+
+    let pixels_offset = u32_at(10) as usize;
+    let header_size = u32_at(14) as usize;
+    let width = u32_at(18) as i32;
+    let height = u32_at(22) as i32;
+    let bits_per_pixel = u16_at(28) as usize;
+    let compression = u32_at(30);
+    let colors_used = u32_at(46) as usize;
+    if header_size < BMP_INFO_HEADER_SIZE || width <= 0 || height == 0 || height == i32::MIN ||
+       compression != BMP_COMPRESSION_NONE || ![1, 4, 8].contains(&bits_per_pixel) {
+      return Err(DisplayError::InvalidImage);
+    }
+
+    // Palette entries are stored as blue, green, red and an unused byte.
+    let palette_start = BMP_FILE_HEADER_SIZE + header_size;
+    let palette_size = if colors_used == 0 { 1 << bits_per_pixel } else { colors_used };
+    let palette = palette_start.checked_add(palette_size * 4)
+      .and_then(|end| data.get(palette_start .. end))
+      .ok_or(DisplayError::InvalidImage)?;
+    let luminance: Vec<u8> = palette.chunks(4)
+      .map(|color| ((299 * color[2] as u32 + 587 * color[1] as u32 + 114 * color[0] as u32 + 500) / 1000) as u8)
+      .collect();
+
+    // Rows are padded to a multiple of 4 bytes and stored bottom-up unless the height is negative.
+    let (width, height, bottom_up) = (width as usize, height.unsigned_abs() as usize, height > 0);
+    let stride = (width * bits_per_pixel).div_ceil(32) * 4;
+    let raster = stride.checked_mul(height)
+      .and_then(|size| pixels_offset.checked_add(size))
+      .and_then(|end| data.get(pixels_offset .. end))
+      .ok_or(DisplayError::InvalidImage)?;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0 .. height {
+      let row_index = if bottom_up { height - 1 - y } else { y };
+      let row = &raster[row_index * stride .. (row_index + 1) * stride];
+      for x in 0 .. width {
+        let bit = x * bits_per_pixel;
+        let shift = 8 - bits_per_pixel - (bit % 8);
+        let index = (row[bit / 8] >> shift) as usize & ((1 << bits_per_pixel) - 1);
+        pixels.push(*luminance.get(index).ok_or(DisplayError::InvalidImage)?);
+      }
+    }
+
+    Ok(Image { width, height, pixels })
+  }
+
+  /// Width of the image in pixels.
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  /// Height of the image in pixels.
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// Returns the luminance of the pixel at (`x`, `y`), or `None` if it is outside of the image.
+  pub fn pixel(&self, x: usize, y: usize) -> Option<u8> {
+    if x < self.width && y < self.height {
+      Some(self.pixels[y * self.width + x])
+    } else {
+      None
+    }
+  }
+
+  /// Returns the image placed on the display as an 8-bit frame in the format accepted by
+  /// `Ssd1325::blit_l8`. Scaled images are resampled by averaging the pixels covered by each
+  /// pixel of the display.
+  pub fn to_l8(&self, placement: Placement) -> [[u8; 128]; 64] {
+    let mut frame = [[0u8; 128]; 64];
+    if self.width == 0 || self.height == 0 {
+      return frame;
+    }
+
+    let (scaled_width, scaled_height) = match placement {
+      Placement::Center => (self.width as f64, self.height as f64),
+      Placement::Fit | Placement::Crop => {
+        let (scale_x, scale_y) = (WIDTH as f64 / self.width as f64, HEIGHT as f64 / self.height as f64);
+        let scale = if placement == Placement::Fit { scale_x.min(scale_y) } else { scale_x.max(scale_y) };
+        ((self.width as f64 * scale).round().max(1.0), (self.height as f64 * scale).round().max(1.0))
+      }
+    };
+    let left = ((WIDTH as f64 - scaled_width) / 2.0).floor();
+    let top = ((HEIGHT as f64 - scaled_height) / 2.0).floor();
+    let (step_x, step_y) = (self.width as f64 / scaled_width, self.height as f64 / scaled_height);
+
+    for (y, line) in frame.iter_mut().enumerate() {
+      let scaled_y = y as f64 - top;
+      if scaled_y < 0.0 || scaled_y >= scaled_height {
+        continue;
+      }
+      let (source_y0, source_y1) = source_span(scaled_y, step_y, self.height);
+      for (x, pixel) in line.iter_mut().enumerate() {
+        let scaled_x = x as f64 - left;
+        if scaled_x < 0.0 || scaled_x >= scaled_width {
+          continue;
+        }
+        let (source_x0, source_x1) = source_span(scaled_x, step_x, self.width);
+        let mut sum = 0usize;
+        for source_y in source_y0 .. source_y1 {
+          let row = &self.pixels[source_y * self.width ..];
+          sum += row[source_x0 .. source_x1].iter().map(|&value| value as usize).sum::<usize>();
+        }
+        let count = (source_x1 - source_x0) * (source_y1 - source_y0);
+        *pixel = ((sum + count / 2) / count) as u8;
+      }
+    }
+    frame
+  }
+
+  /// Returns the image placed on the display as a 4-bit frame in the format accepted by
+  /// `Ssd1325::blit_l4`, reduced to 16 gray levels using `quantization`.
+  pub fn to_l4(&self, placement: Placement, quantization: Quantization) -> [[u8; 64]; 64] {
+    quantize_l8(&self.to_l8(placement), quantization)
+  }
+
+  /// Returns the image placed on the display as a 1-bit frame in the format accepted by
+  /// `Ssd1325::blit_l1`, with pixels at or above `threshold` on and all others off.
+  pub fn to_l1(&self, placement: Placement, threshold: u8) -> [[u8; 16]; 64] {
+    let mut frame = [[0u8; 16]; 64];
+    for (line, packed) in self.to_l8(placement).iter().zip(frame.iter_mut()) {
+      for (x, &value) in line.iter().enumerate() {
+        if value >= threshold {
+          packed[x / 8] |= 0x80 >> (x % 8);
+        }
+      }
+    }
+    frame
+  }
+
+}
+
+/// Returns the range of source pixels covered by the scaled pixel at `position`, where each
+/// scaled pixel covers `step` source pixels, within a source of `length` pixels.
+fn source_span(position: f64, step: f64, length: usize) -> (usize, usize) {
+  let start = ((position * step).floor() as usize).min(length - 1);
+  let end = (((position + 1.0) * step).ceil() as usize).clamp(start + 1, length);
+  (start, end)
+}
+
+/// Returns the 8-bit luminance of a sample `value` out of `maximum`.
+fn scale_sample(value: usize, maximum: usize) -> Result<u8, DisplayError> {
+  if value > maximum {
+    return Err(DisplayError::InvalidImage);
+  }
+  Ok(((value * 255 + maximum / 2) / maximum) as u8)
+}
+
+/// Reads the header and pixels of a Netpbm image.
+struct NetpbmReader<'a> {
+  data: &'a [u8],
+  position: usize,
+}
+
+impl<'a> NetpbmReader<'a> {
+
+  /// Skips whitespace and comments, which run from `#` to the end of the line.
+  fn skip_whitespace(&mut self) {
+    while let Some(&byte) = self.data.get(self.position) {
+      if byte == b'#' {
+        while self.data.get(self.position).is_some_and(|&byte| byte != b'\n') {
+          self.position += 1;
+        }
+      } else if byte.is_ascii_whitespace() {
+        self.position += 1;
+      } else {
+        break;
+      }
+    }
+  }
+
+  /// Returns the next whitespace-separated token.
+  fn token(&mut self) -> Result<&'a [u8], DisplayError> {
+    self.skip_whitespace();
+    let start = self.position;
+    while self.data.get(self.position).is_some_and(|byte| !byte.is_ascii_whitespace() && *byte != b'#') {
+      self.position += 1;
+    }
+    if start == self.position {
+      return Err(DisplayError::InvalidImage);
+    }
+    Ok(&self.data[start .. self.position])
+  }
+
+  /// Returns the next token as a decimal integer.
+  fn integer(&mut self) -> Result<usize, DisplayError> {
+    let token = self.token()?;
+    ::std::str::from_utf8(token).ok()
+      .and_then(|token| token.parse().ok())
+      .ok_or(DisplayError::InvalidImage)
+  }
+
+  /// Returns the next `0` or `1` of a plain PBM raster, which need not be separated.
+  fn bit(&mut self) -> Result<u8, DisplayError> {
+    self.skip_whitespace();
+    let bit = match self.data.get(self.position) {
+      Some(b'0') => 0,
+      Some(b'1') => 1,
+      _ => return Err(DisplayError::InvalidImage),
+    };
+    self.position += 1;
+    Ok(bit)
+  }
+
+  /// Returns the `size` bytes of a binary raster, which follows a single whitespace byte.
+  fn raster(&mut self, size: usize) -> Result<&'a [u8], DisplayError> {
+    if !self.data.get(self.position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+      return Err(DisplayError::InvalidImage);
+    }
+    let start = self.position + 1;
+    let raster = start.checked_add(size)
+      .and_then(|end| self.data.get(start .. end))
+      .ok_or(DisplayError::InvalidImage)?;
+    self.position = start + size;
+    Ok(raster)
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Returns a BMP file with a grayscale palette of `1 << bits_per_pixel` entries, where each
+  /// of the `rows` is given top-down and stored bottom-up.
+  fn bmp(width: u32, bits_per_pixel: u16, rows: &[&[u8]]) -> Vec<u8> {
+    let colors = 1usize << bits_per_pixel;
+    let stride = (width as usize * bits_per_pixel as usize).div_ceil(32) * 4;
+    let pixels_offset = BMP_FILE_HEADER_SIZE + BMP_INFO_HEADER_SIZE + colors * 4;
+
+    let mut data = b"BM".to_vec();
+    data.extend_from_slice(&((pixels_offset + stride * rows.len()) as u32).to_le_bytes());
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&(pixels_offset as u32).to_le_bytes());
+    data.extend_from_slice(&(BMP_INFO_HEADER_SIZE as u32).to_le_bytes());
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&(rows.len() as u32).to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&bits_per_pixel.to_le_bytes());
+    data.extend_from_slice(&[0; 24]);
+    for index in 0 .. colors {
+      let level = (index * 255 / (colors - 1)) as u8;
+      data.extend_from_slice(&[level, level, level, 0]);
+    }
+    for row in rows.iter().rev() {
+      let mut padded = row.to_vec();
+      padded.resize(stride, 0);
+      data.extend_from_slice(&padded);
+    }
+    data
+  }
+
+  #[test]
+  fn test_pbm() {
+    let plain = Image::decode(b"P1\n# A comment\n3 2\n1 0 1\n010").unwrap();
+    assert_eq!((plain.width(), plain.height()), (3, 2));
+    assert_eq!(plain.pixels, [0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF]);
+
+    // Binary rows are padded to whole bytes.
+    let raw = Image::decode(b"P4 3 2\n\xA0\x40").unwrap();
+    assert_eq!(raw, plain);
+
+    assert_eq!(Image::decode(b"P1 3 2 1 0 1 0"), Err(DisplayError::InvalidImage));
+    assert_eq!(Image::decode(b"P4 3 2\n\xA0"), Err(DisplayError::InvalidImage));
+  }
+
+  #[test]
+  fn test_pgm() {
+    let plain = Image::decode(b"P2 2 2 15 0 5 10 15").unwrap();
+    assert_eq!(plain.pixels, [0, 85, 170, 255]);
+
+    let raw = Image::decode(b"P5 2 1 255\n\x12\x34").unwrap();
+    assert_eq!(raw.pixels, [0x12, 0x34]);
+
+    // Samples with a maximum above 255 are two bytes, most significant first.
+    let wide = Image::decode(b"P5 2 1 65535\n\xFF\xFF\x80\x00").unwrap();
+    assert_eq!(wide.pixels, [0xFF, 0x80]);
+
+    assert_eq!(Image::decode(b"P2 1 1 15 16"), Err(DisplayError::InvalidImage));
+    assert_eq!(Image::decode(b"P3 1 1 255 0 0 0"), Err(DisplayError::InvalidImage));
+    assert_eq!(Image::decode(b"P5 0 1 255\n"), Err(DisplayError::InvalidImage));
+    assert_eq!(Image::decode(b"P2 3037000499 3037000499 255"), Err(DisplayError::InvalidImage));
+    assert_eq!(Image::decode(b"P4 4294967295 4294967295\n"), Err(DisplayError::InvalidImage));
+  }
+
+  #[test]
+  fn test_bmp() {
+    let mono = Image::decode(&bmp(3, 1, &[&[0b1010_0000], &[0b0100_0000]])).unwrap();
+    assert_eq!((mono.width(), mono.height()), (3, 2));
+    assert_eq!(mono.pixels, [0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00]);
+
+    let gray4 = Image::decode(&bmp(3, 4, &[&[0x0F, 0x50]])).unwrap();
+    assert_eq!(gray4.pixels, [0x00, 0xFF, 0x55]);
+
+    let gray8 = Image::decode(&bmp(2, 8, &[&[0x00, 0x80], &[0xFF, 0x01]])).unwrap();
+    assert_eq!(gray8.pixels, [0x00, 0x80, 0xFF, 0x01]);
+
+    // Compressed and 24-bit images are not supported.
+    let mut compressed = bmp(2, 8, &[&[0, 0]]);
+    compressed[30] = 1;
+    assert_eq!(Image::decode(&compressed), Err(DisplayError::InvalidImage));
+    let mut true_color = bmp(2, 8, &[&[0, 0]]);
+    true_color[28] = 24;
+    assert_eq!(Image::decode(&true_color), Err(DisplayError::InvalidImage));
+
+    // Truncated pixel data is rejected.
+    let truncated = bmp(2, 8, &[&[0, 0]]);
+    assert_eq!(Image::decode(&truncated[.. truncated.len() - 1]), Err(DisplayError::InvalidImage));
+  }
+
+  #[test]
+  fn test_center() {
+    let image = Image::new(2, 2, vec![0x10, 0x20, 0x30, 0x40]).unwrap();
+    let frame = image.to_l8(Placement::Center);
+    assert_eq!(frame[31][63 .. 65], [0x10, 0x20]);
+    assert_eq!(frame[32][63 .. 65], [0x30, 0x40]);
+    assert_eq!(frame.iter().flat_map(|line| line.iter()).filter(|&&value| value != 0).count(), 4);
+
+    // Larger images are cropped around their center.
+    let mut pixels = vec![0u8; 130 * 66];
+    pixels[130 + 1] = 0xFF;
+    let large = Image::new(130, 66, pixels).unwrap();
+    assert_eq!(large.to_l8(Placement::Center)[0][0], 0xFF);
+  }
+
+  #[test]
+  fn test_fit_and_crop() {
+    // A 4x4 image with a white left half.
+    let image = Image::new(4, 4, [0xFF, 0xFF, 0x00, 0x00].repeat(4)).unwrap();
+
+    // Fitting scales to 64x64, centered between black bars.
+    let fit = image.to_l8(Placement::Fit);
+    assert_eq!(fit[10][31], 0x00);
+    assert_eq!(fit[10][32], 0xFF);
+    assert_eq!(fit[10][63], 0xFF);
+    assert_eq!(fit[10][64], 0x00);
+    assert_eq!(fit[10][127], 0x00);
+
+    // Cropping scales to 128x128, keeping the middle rows.
+    let crop = image.to_l8(Placement::Crop);
+    assert!(crop.iter().all(|line| line[.. 64].iter().all(|&value| value == 0xFF)));
+    assert!(crop.iter().all(|line| line[64 ..].iter().all(|&value| value == 0x00)));
+
+    // Downscaling averages the covered pixels.
+    let stripes = Image::new(256, 128, [0xFF, 0x00].repeat(128 * 128)).unwrap();
+    assert_eq!(stripes.to_l8(Placement::Fit)[0][0], 0x80);
+  }
+
+  #[test]
+  fn test_packed_frames() {
+    let image = Image::new(128, 64, (0 .. 128 * 64).map(|index| (index % 128) as u8 * 2).collect()).unwrap();
+    let mono = image.to_l1(Placement::Center, 0x80);
+    assert_eq!(mono[0][0 .. 8], [0x00; 8]);
+    assert_eq!(mono[0][8 .. 16], [0xFF; 8]);
+
+    let gray = image.to_l4(Placement::Center, Quantization::Nearest);
+    assert_eq!(gray[0][0] >> 4, 0x0);
+    assert_eq!(gray[0][63] & 0x0F, 0xF);
+  }
+
+}
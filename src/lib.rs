@@ -1,6 +1,52 @@
 
 use std::{error, fmt, io, thread, time};
 
+#[cfg(feature = "embedded-graphics")]
+extern crate embedded_graphics;
+
+mod antialias;
+mod config;
+#[cfg(feature = "emulator")]
+mod disassembler;
+mod draw;
+#[cfg(feature = "emulator")]
+mod emulator;
+mod fonts;
+mod framebuffer;
+mod glyph_set;
+#[cfg(feature = "embedded-graphics")]
+mod graphics;
+mod gray;
+mod image;
+mod panel;
+mod portrait;
+mod quantize;
+#[cfg(feature = "emulator")]
+mod snapshot;
+#[cfg(feature = "emulator")]
+mod terminal;
+mod text;
+
+pub use antialias::{AtlasGlyph, CoverageFont, CoverageGlyph, GlyphAtlas};
+pub use config::{AddressIncrement, ComRemap, CurrentRange, DisplayConfig, DisplayConfigBuilder, Orientation, Remap};
+#[cfg(feature = "emulator")]
+pub use disassembler::{disassemble, Command};
+pub use draw::{Canvas, Fill, Pattern, Style};
+#[cfg(feature = "emulator")]
+pub use emulator::{Emulator, Glass, HorizontalScroll, ViewMode, Violation};
+pub use framebuffer::{FrameBuffer, FrameBufferL1, FrameBufferL4};
+pub use glyph_set::GlyphSet;
+pub use gray::{gray_table_for_gamma, gray_table_for_luminance, gray_table_pulse_widths, validate_gray_table, GrayTablePreset};
+pub use image::{Image, Placement};
+pub use panel::PanelProfile;
+pub use portrait::{rotate_l1, rotate_l4, PortraitRotation};
+pub use quantize::{quantize_l8, Quantization};
+#[cfg(feature = "emulator")]
+pub use terminal::{render_ansi, PreviewStyle, TerminalPreview};
+pub use text::{text_width, wrap_text, Alignment, BuiltinFont, Font, Glyph, TextStyle};
+
+// Some commands are only decoded by the emulator, never sent by the driver.
+#[cfg_attr(not(feature = "emulator"), allow(dead_code))]
 mod commands {
   /// 10.1.1 Set Column Address
   /// Note: Each pixel takes 4 bits in display memory so `(end-start)` should be `h_pixels/2`.
@@ -20,6 +66,10 @@ mod commands {
   /// # Arguments
   /// * contrast: `u8` Contrast current from `0` to `0x7F`.
   pub const SETCONTRAST: u8 = 0x81;
+  /// 10.1.4 Set Current Range to Quarter.
+  pub const SETCURRENT_QUARTER: u8 = 0x84;
+  /// 10.1.4 Set Current Range to Half.
+  pub const SETCURRENT_HALF: u8 = 0x84 + 0x01;
   /// 10.1.4 Set Current Range to Full.
   pub const SETCURRENT_FULL: u8 = 0x84 + 0x03;
   /// 10.1.5 Set Re-Map.
@@ -47,6 +97,10 @@ mod commands {
   pub const SETOFFSET: u8 = 0xA2;
   /// 10.1.8.1 Enter Normal Display Mode
   pub const NORMALDISPLAY: u8 = 0xA4;
+  /// 10.1.8.2 Enter Entire Display On Mode
+  pub const ALLONDISPLAY: u8 = 0xA5;
+  /// 10.1.8.3 Enter Entire Display Off Mode
+  pub const ALLOFFDISPLAY: u8 = 0xA6;
   /// 10.1.8.4 Enter Inverse Display Mode
   pub const INVERTDISPLAY: u8 = 0xA7;
   /// 10.1.9 Set Multiplex Ratio
@@ -122,6 +176,12 @@ mod commands {
   ///   * `------1-` To enable wrap-around in X-direction on copy, set to `1`.
   ///   * `-------0` To enable fill rectangle on draw, set to `1`.
   pub const GFXACCEL: u8 = 0x23;
+  /// `GFXACCEL` option bit enabling fill rectangle on draw.
+  pub const GFXACCEL_FILL: u8 = 0x01;
+  /// `GFXACCEL` option bit enabling wrap-around in X-direction on copy.
+  pub const GFXACCEL_WRAP: u8 = 0x02;
+  /// `GFXACCEL` option bit enabling reverse during copying.
+  pub const GFXACCEL_REVERSE: u8 = 0x10;
   /// 10.2.2 Draw Rectangle
   ///
   /// # Arguments
@@ -131,6 +191,34 @@ mod commands {
   ///   * end_row: `u8` Ending row coordinates.
   ///   * pattern: `u8` Grayscale pattern to fill with.
   pub const DRAWRECT: u8 = 0x24;
+  /// 10.2.3 Copy
+  ///
+  /// # Arguments
+  ///   * start_col: `u8` Starting column coordinates of the source.
+  ///   * start_row: `u8` Starting row coordinates of the source.
+  ///   * end_col: `u8` Ending column coordinates of the source.
+  ///   * end_row: `u8` Ending row coordinates of the source.
+  ///   * new_col: `u8` Starting column coordinates of the destination.
+  ///   * new_row: `u8` Starting row coordinates of the destination.
+  pub const COPY: u8 = 0x25;
+  /// 10.2.4 Horizontal Scroll Setup
+  ///
+  /// # Arguments
+  ///   * offset: `u8` Number of columns scrolled per step.
+  ///   * start_row: `u8` Start row address.
+  ///   * rows: `u8` Number of rows to be scrolled. `start_row + rows` must not exceed 80.
+  ///   * interval: `u8` Time interval between each scroll step as follows.
+  ///     * `0x00` = 6 frames.
+  ///     * `0x01` = 10 frames.
+  ///     * `0x02` = 100 frames.
+  ///     * `0x03` = 200 frames.
+  pub const SETUPHSCROLL: u8 = 0x26;
+  /// 10.2.5 Deactivate Scrolling
+  pub const DEACTIVATESCROLL: u8 = 0x2E;
+  /// 10.2.6 Activate Scrolling
+  pub const ACTIVATESCROLL: u8 = 0x2F;
+  /// 10.1.17 No Operation
+  pub const NOP: u8 = 0xE3;
 }
 
 /// Errors which may occur interacting with the display.
@@ -138,23 +226,112 @@ mod commands {
 pub enum DisplayError {
   /// It was not possible to send all the necessary data to the display.
   WriteFailed,
+  /// The requested region is empty or extends beyond the bounds of the display.
+  RegionOutOfBounds,
+  /// The size of the supplied pixel buffer does not match the requested region.
+  BufferSizeMismatch,
+  /// An argument is outside of the range accepted by the display.
+  ArgumentOutOfRange,
+  /// The region does not start and end on the two-pixel column boundaries of display RAM.
+  UnalignedRegion,
+  /// A display configuration parameter is outside of the range accepted by the display.
+  InvalidConfiguration,
+  /// The font data is malformed or in an unsupported format.
+  InvalidFont,
+  /// The image data is malformed or in an unsupported format.
+  InvalidImage,
+}
+
+impl DisplayError {
+  /// Returns a human-readable description of the error.
+  fn message(&self) -> &'static str {
+    match *self {
+      DisplayError::WriteFailed =>
+        "write failed: unable to send complete sequence to display",
+      DisplayError::RegionOutOfBounds =>
+        "region out of bounds: region is empty or exceeds the display dimensions",
+      DisplayError::BufferSizeMismatch =>
+        "buffer size mismatch: pixel buffer does not match the region dimensions",
+      DisplayError::ArgumentOutOfRange =>
+        "argument out of range: value is not supported by the display",
+      DisplayError::UnalignedRegion =>
+        "unaligned region: region must start on an even and end on an odd column",
+      DisplayError::InvalidConfiguration =>
+        "invalid configuration: parameter is not supported by the display",
+      DisplayError::InvalidFont =>
+        "invalid font: font data is malformed or in an unsupported format",
+      DisplayError::InvalidImage =>
+        "invalid image: image data is malformed or in an unsupported format",
+    }
+  }
 }
 
 impl fmt::Display for DisplayError {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "{}", (self as &error::Error).description())
+    write!(f, "{}", self.message())
   }
 }
 
 impl error::Error for DisplayError {
   fn description(&self) -> &str {
-    match self {
-      &DisplayError::WriteFailed =>
-        "write failed: unable to send complete sequence to display",
-    }
+    self.message()
   }
 }
 
+/// Width of the display in pixels.
+pub const WIDTH: usize = 128;
+
+/// Height of the display in pixels.
+pub const HEIGHT: usize = 64;
+
+/// Number of rows of display RAM. Rows beyond the 64 shown on the panel at once can be brought
+/// into view with `Ssd1325::set_vertical_scroll`.
+const RAM_ROWS: u8 = 80;
+
+/// A rectangle in display pixel coordinates, with both corners inclusive.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Rect {
+  /// Left-most column.
+  pub x0: u8,
+  /// Top-most row.
+  pub y0: u8,
+  /// Right-most column.
+  pub x1: u8,
+  /// Bottom-most row.
+  pub y1: u8,
+}
+
+/// A point in display pixel coordinates.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Point {
+  /// Column, from left to right.
+  pub x: u8,
+  /// Row, from top to bottom.
+  pub y: u8,
+}
+
+/// Options for copying a region of display RAM with `Ssd1325::copy_region`.
+#[derive(Copy,Clone,Debug,Default,Eq,PartialEq)]
+pub struct CopyOptions {
+  /// Invert the gray level of each copied pixel.
+  pub reverse: bool,
+  /// Wrap columns past the right edge of the display around to the left edge.
+  pub wrap_x: bool,
+}
+
+/// Time interval between steps of a horizontal scroll, in display frames.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum ScrollInterval {
+  /// Scroll every 6 frames.
+  Frames6,
+  /// Scroll every 10 frames.
+  Frames10,
+  /// Scroll every 100 frames.
+  Frames100,
+  /// Scroll every 200 frames.
+  Frames200,
+}
+
 /// Mode of the primary communication channel.
 #[derive(Copy,Clone,Debug,Eq,PartialEq)]
 pub enum DisplayMode {
@@ -172,15 +349,25 @@ pub enum DisplayMode {
 pub trait ControlChannel {
   /// Put the display communication channel in the specified `mode`.
   /// Once the command is executed the display must be left in a state other than `Reset`.
-  fn run_in_mode(&mut self, mode: DisplayMode, f: &mut FnMut() -> Result<(),Box<error::Error>>) -> Result<(),Box<error::Error>>;
+  fn run_in_mode(&mut self, mode: DisplayMode, f: &mut dyn FnMut() -> Result<(),Box<dyn error::Error>>) -> Result<(),Box<dyn error::Error>>;
 }
 
 /// An SSD1325 display interface command adapter.
 pub struct Ssd1325<'a> {
   /// Transport for sending data to the display.
-  transport: &'a mut io::Write,
+  transport: &'a mut dyn io::Write,
   /// Transport for side-band control data.
-  control_channel: &'a mut ControlChannel,
+  control_channel: &'a mut dyn ControlChannel,
+  /// Copy of display RAM as last written by the driver, in the format accepted by `blit_l4`.
+  shadow: [[u8; 64]; 64],
+  /// Graphic acceleration options last sent to the display.
+  gfx_accel: u8,
+  /// Display start line last sent to the display.
+  start_line: u8,
+  /// Contrast current last sent to the display.
+  contrast: u8,
+  /// Configuration last used to initialize the display.
+  config: DisplayConfig,
 }
 
 impl<'a> Ssd1325<'a> {
@@ -191,80 +378,58 @@ impl<'a> Ssd1325<'a> {
   /// The `control_channel` is used to put the display into a given mode before writing data.
   /// Typically, this is done using sysfs gpio.
   /// The display must be initialized prior to use, and is left Off.
-  pub fn new(transport: &'a mut io::Write, control_channel: &'a mut ControlChannel) -> Self {
+  pub fn new(transport: &'a mut dyn io::Write, control_channel: &'a mut dyn ControlChannel) -> Self {
     Ssd1325 {
-      transport: transport,
-      control_channel: control_channel,
+      transport,
+      control_channel,
+      shadow: [[0u8; 64]; 64],
+      gfx_accel: commands::GFXACCEL_FILL,
+      start_line: 0,
+      contrast: DisplayConfig::default().contrast(),
+      config: DisplayConfig::default(),
     }
   }
 
-  /// Resets and initializes the display. Blocks for approximately 600ms.
-  pub fn init(&mut self) -> Result<(),Box<error::Error>> {
-    use commands::*;
-
-    // The initialization sequence.
-    const INIT_SEQUENCE: &'static [u8] = &[
-      // Turn the display off.
-      DISPLAYOFF,
-      // Set the oscillator division.
-      SETCLOCK, 0xF1,
-      // Set the multiplex ratio to 1/64 duty cycle.
-      SETMULTIPLEX, 0x3F,
-      // Set the display offset to 76.
-      SETOFFSET, 0x4C,
-      // Set the start line to 0.
-      SETSTARTLINE, 0x00,
-      // Set Master Config to DC/DC Converter.
-      MASTERCONFIG, 0x02,
-      // Set segment remap to 0x50: *1010000 (COM split, remap bottom-up, horiz. increment, no nibble remap).
-      SETREMAP, 0x50,
-      // Set full current range.
-      SETCURRENT_FULL,
-      // Set the gray color palette.
-      SETGRAYTABLE, 0x01, 0x11, 0x22, 0x32, 0x43, 0x54, 0x65, 0x76,
-      // Set the contrast to maximum.
-      SETCONTRAST, 0x7F,
-      // Set the row period.
-      SETROWPERIOD, 0x51,
-      // Set the phase length.
-      SETPHASELEN, 0x55,
-      // Set the precharge comparator to 2.
-      SETPRECHARGECOMP, 0x02,
-      // Enable the precharge comparator.
-      SETPRECHARGECOMPENABLE, 0x28,
-      // Set the high voltage level of the COM pin (`0x1C = 0.80 * Vref`).
-      SETVCOMLEVEL, 0x1C,
-      // Set the low voltage level of the SEG pin. Value may be wrong.
-      SETVSL, (0x0D | 0x02),
-      // Set the display to non-inverted configuration.
-      NORMALDISPLAY,
-      // Turn on the Draw Rect command only, used to clear the screen.
-      GFXACCEL, 0x01,
-    ];
+  /// Resets and initializes the display with the default configuration, tuned for the
+  /// Adafruit 2.7" 128x64 module. Blocks for approximately 600ms.
+  pub fn init(&mut self) -> Result<(),Box<dyn error::Error>> {
+    self.init_with(DisplayConfig::default())
+  }
 
+  /// Resets and initializes the display with `config`, which may be obtained from a
+  /// `PanelProfile` for known modules. Blocks for approximately 600ms.
+  pub fn init_with(&mut self, config: DisplayConfig) -> Result<(),Box<dyn error::Error>> {
     // Reset the display.
     self.reset()?;
 
     // Send the initialization sequence in command mode to the display.
-    self.write_sequence(DisplayMode::Command, INIT_SEQUENCE)
+    self.write_sequence(DisplayMode::Command, &config.init_sequence())?;
+    self.gfx_accel = commands::GFXACCEL_FILL;
+    self.start_line = config.start_line();
+    self.contrast = config.contrast();
+    self.config = config;
+    Ok(())
   }
 
   /// Clears the display.
-  pub fn clear(&mut self) -> Result<(),Box<error::Error>> {
+  pub fn clear(&mut self) -> Result<(),Box<dyn error::Error>> {
     use commands::*;
 
     // Clear sequence utilizing graphics acceleration.
-    const CLEAR_SEQUENCE: &'static [u8] = &[
+    const CLEAR_SEQUENCE: &[u8] = &[
       // Clear the display.
       DRAWRECT, 0x00, 0x00, 0x3F, 0x3F, 0x00,
     ];
 
     // Send the clear sequence in command mode to the display.
-    self.write_sequence(DisplayMode::Command, CLEAR_SEQUENCE)
+    self.set_graphic_acceleration(GFXACCEL_FILL)?;
+    self.write_sequence(DisplayMode::Command, CLEAR_SEQUENCE)?;
+    self.shadow = [[0u8; 64]; 64];
+    Ok(())
   }
 
   /// Turn the display on or off. Configured to Off after initialization.
-  pub fn set_on(&mut self, on: bool) -> Result<(),Box<error::Error>> {
+  pub fn set_on(&mut self, on: bool) -> Result<(),Box<dyn error::Error>> {
     match on {
       true =>
         self.write_sequence(DisplayMode::Command, &[commands::DISPLAYON]),
@@ -274,7 +439,7 @@ impl<'a> Ssd1325<'a> {
   }
 
   /// Make the display inverted or normal. Configured to Normal after initialization.
-  pub fn set_inverted(&mut self, inverted: bool) -> Result<(),Box<error::Error>> {
+  pub fn set_inverted(&mut self, inverted: bool) -> Result<(),Box<dyn error::Error>> {
     match inverted {
       true =>
         self.write_sequence(DisplayMode::Command, &[commands::INVERTDISPLAY]),
@@ -283,39 +448,441 @@ impl<'a> Ssd1325<'a> {
     }
   }
 
-  /// Send an entire bitmap frame to the display.
-  /// The input image must be a 1-bit bitmap image arranged as 64 rows of 128 pixels.
-  /// Pixels must be packed 8 per byte, with the most significant bit corresponding to
-  /// the first pixel in the group (i.e. `0b1234567`).
-  pub fn blit_l1(&mut self, frame: &[[u8; 16]; 64]) -> Result<(),Box<error::Error>> {
+  /// Sets the orientation of the image on the panel, relative to the mounting described by the
+  /// display configuration. The orientation is applied by the display through the Set Re-Map
+  /// and Set Display Offset commands, so display RAM is left unchanged and frames, regions and
+  /// graphic acceleration commands continue to use upright coordinates. The display start line
+  /// is unaffected, so `set_vertical_scroll` continues to scroll the image the same way.
+  /// Configured to the orientation of the display configuration after initialization.
+  pub fn set_orientation(&mut self, orientation: Orientation) -> Result<(),Box<dyn error::Error>> {
     use commands::*;
 
-    // Clear sequence utilizing graphics acceleration.
-    const BLIT_PREAMBLE_SEQUENCE: &'static [u8] = &[
-      // Set the column address range to 0x00...0x3F. Each pixel takes 4 bits.
-      SETCOLADDR, 0x00, 0x3F,
-      // Set the row address range to 0x00...0x3F. There are 64 rows.
-      SETROWADDR, 0x00, 0x3F,
+    let config = self.config.to_builder().orientation(orientation).build()?;
+    let sequence = [
+      SETREMAP, config.oriented_remap().to_byte(),
+      SETOFFSET, config.oriented_display_offset(),
     ];
+    self.write_sequence(DisplayMode::Command, &sequence)?;
+    self.config = config;
+    Ok(())
+  }
+
+  /// Sets the contrast current from `0x00` to `0x7F`, adjusting the brightness of the display.
+  /// Configured to the contrast of the display configuration after initialization.
+  ///
+  /// # Returns
+  /// `DisplayError::ArgumentOutOfRange` if `contrast` is above `0x7F`.
+  pub fn set_contrast(&mut self, contrast: u8) -> Result<(),Box<dyn error::Error>> {
+    if contrast > 0x7F {
+      return Err(Box::new(DisplayError::ArgumentOutOfRange));
+    }
+    self.write_sequence(DisplayMode::Command, &[commands::SETCONTRAST, contrast])?;
+    self.contrast = contrast;
+    Ok(())
+  }
+
+  /// Sets the current range, scaling the brightness set by the contrast current.
+  /// Configured to the current range of the display configuration after initialization.
+  pub fn set_current_range(&mut self, range: CurrentRange) -> Result<(),Box<dyn error::Error>> {
+    match range {
+      CurrentRange::Quarter =>
+        self.write_sequence(DisplayMode::Command, &[commands::SETCURRENT_QUARTER]),
+      CurrentRange::Half =>
+        self.write_sequence(DisplayMode::Command, &[commands::SETCURRENT_HALF]),
+      CurrentRange::Full =>
+        self.write_sequence(DisplayMode::Command, &[commands::SETCURRENT_FULL]),
+    }
+  }
 
+  /// Gradually changes the contrast current from its current value to `contrast` over
+  /// approximately `duration`, one step at a time. Blocks until the fade completes.
+  ///
+  /// # Returns
+  /// `DisplayError::ArgumentOutOfRange` if `contrast` is above `0x7F`.
+  pub fn fade_to(&mut self, contrast: u8, duration: time::Duration) -> Result<(),Box<dyn error::Error>> {
+    if contrast > 0x7F {
+      return Err(Box::new(DisplayError::ArgumentOutOfRange));
+    }
+
+    let steps = (i32::from(contrast) - i32::from(self.contrast)).unsigned_abs();
+    if steps == 0 {
+      return Ok(());
+    }
+
+    let step_delay = duration / steps;
+    while self.contrast != contrast {
+      let next = if self.contrast < contrast { self.contrast + 1 } else { self.contrast - 1 };
+      self.set_contrast(next)?;
+      if self.contrast != contrast {
+        thread::sleep(step_delay);
+      }
+    }
+    Ok(())
+  }
+
+  /// Sets the gray scale `table`, defining the pulse width of each of the 16 gray levels.
+  /// Each nibble must be from `0x0` to `0x7`, as described in `validate_gray_table`.
+  /// Tables for common response curves can be obtained from a `GrayTablePreset`.
+  /// Configured to the gray table of the display configuration after initialization.
+  ///
+  /// # Returns
+  /// `DisplayError::ArgumentOutOfRange` if any nibble of `table` is above `0x7`.
+  pub fn set_gray_table(&mut self, table: [u8; 8]) -> Result<(),Box<dyn error::Error>> {
+    validate_gray_table(&table)?;
+
+    let mut sequence = [0u8; 9];
+    sequence[0] = commands::SETGRAYTABLE;
+    sequence[1..].copy_from_slice(&table);
+    self.write_sequence(DisplayMode::Command, &sequence)?;
+    self.config.gray_table = table;
+    Ok(())
+  }
+
+  /// Send an entire bitmap frame to the display.
+  /// The input image must be a 1-bit bitmap image arranged as 64 rows of 128 pixels.
+  /// Pixels must be packed 8 per byte, with the most significant bit corresponding to
+  /// the first pixel in the group (i.e. `0b1234567`).
+  pub fn blit_l1(&mut self, frame: &[[u8; 16]; 64]) -> Result<(),Box<dyn error::Error>> {
     // Write the blit preamble sequence to the display.
-    self.write_sequence(DisplayMode::Command, BLIT_PREAMBLE_SEQUENCE)?;
+    self.write_full_frame_preamble()?;
 
     // Unpack each line of display data and send it over the transport in Data mode.
     let mut sequence = [0u8; 64];
-    for line in frame.iter() {
+    for (index, line) in frame.iter().enumerate() {
       unpack_line_for_display(line, &mut sequence);
       self.write_sequence(DisplayMode::Data, &sequence)?;
+      self.shadow[index] = sequence;
+    }
+
+    Ok(())
+  }
+
+  /// Send an entire 16-level grayscale frame to the display.
+  /// The input image must be a 4-bit grayscale image arranged as 64 rows of 128 pixels.
+  /// Pixels must be packed 2 per byte, with the most significant nibble corresponding to
+  /// the first pixel in the pair (i.e. `0bAAAABBBB`). This matches the layout of display RAM,
+  /// so each line is sent to the display as-is.
+  pub fn blit_l4(&mut self, frame: &[[u8; 64]; 64]) -> Result<(),Box<dyn error::Error>> {
+    // Write the blit preamble sequence to the display.
+    self.write_full_frame_preamble()?;
+
+    // Send each line of display data over the transport in Data mode.
+    for (index, line) in frame.iter().enumerate() {
+      self.write_sequence(DisplayMode::Data, line)?;
+      self.shadow[index] = *line;
+    }
+
+    Ok(())
+  }
+
+  /// Send an entire 8-bit grayscale frame to the display.
+  /// The input image must be an 8-bit luminance image arranged as 64 rows of 128 pixels, one
+  /// pixel per byte. The image is reduced to the 16 gray levels supported by the display
+  /// using the specified `quantization` strategy and sent using `blit_l4`.
+  pub fn blit_l8(&mut self, frame: &[[u8; 128]; 64], quantization: Quantization) -> Result<(),Box<dyn error::Error>> {
+    let quantized = quantize_l8(frame, quantization);
+    self.blit_l4(&quantized)
+  }
+
+  /// Send an entire bitmap frame to the display in portrait orientation.
+  /// The input image must be a 1-bit bitmap image arranged as 128 rows of 64 pixels, packed as
+  /// in `blit_l1`. The image is rotated onto the panel by `rotation` and sent using `blit_l1`.
+  pub fn blit_l1_portrait(&mut self, frame: &[[u8; 8]; 128], rotation: PortraitRotation) -> Result<(),Box<dyn error::Error>> {
+    let rotated = rotate_l1(frame, rotation);
+    self.blit_l1(&rotated)
+  }
+
+  /// Send an entire 16-level grayscale frame to the display in portrait orientation.
+  /// The input image must be a 4-bit grayscale image arranged as 128 rows of 64 pixels, packed
+  /// as in `blit_l4`. The image is rotated onto the panel by `rotation` and sent using `blit_l4`.
+  pub fn blit_l4_portrait(&mut self, frame: &[[u8; 32]; 128], rotation: PortraitRotation) -> Result<(),Box<dyn error::Error>> {
+    let rotated = rotate_l4(frame, rotation);
+    self.blit_l4(&rotated)
+  }
+
+  /// Send a rectangular region of a 16-level grayscale image to the display.
+  /// The region is `width` pixels wide and `height` pixels tall with its top-left corner at
+  /// (`x`, `y`). The `pixels` slice must contain exactly `width * height` gray levels from
+  /// `0x0` to `0xF`, one per byte, arranged in rows from top to bottom.
+  ///
+  /// Display RAM stores two pixels per byte, so a region starting or ending on an odd pixel
+  /// is widened to the enclosing byte boundary. The neighbouring pixel is taken from the
+  /// driver's copy of display RAM, which reflects the last `clear()` or blit. Display RAM
+  /// contents are undefined after `init()`, so `clear()` should be called before the first
+  /// region blit.
+  ///
+  /// # Returns
+  /// `DisplayError::RegionOutOfBounds` if the region is empty or does not fit on the display,
+  /// or `DisplayError::BufferSizeMismatch` if `pixels` is not `width * height` bytes long.
+  pub fn blit_region(&mut self, x: u8, y: u8, width: u8, height: u8, pixels: &[u8]) -> Result<(),Box<dyn error::Error>> {
+    let (x, y, width, height) = (x as usize, y as usize, width as usize, height as usize);
+    if width == 0 || height == 0 || x + width > 128 || y + height > 64 {
+      return Err(Box::new(DisplayError::RegionOutOfBounds));
+    }
+    if pixels.len() != width * height {
+      return Err(Box::new(DisplayError::BufferSizeMismatch));
+    }
+
+    // Merge the region into the copy of display RAM and send the bytes covering it.
+    merge_region_l4(&mut self.shadow, x, y, width, pixels);
+    self.write_shadow_window(x / 2, (x + width - 1) / 2, y, y + height - 1)
+  }
+
+  /// Fills the rectangle with corners (`x0`, `y0`) and (`x1`, `y1`) inclusive with the gray
+  /// level `gray`, from `0x0` to `0xF`, using the Draw Rectangle graphic acceleration command.
+  ///
+  /// The display addresses columns in units of two pixels, so columns entirely within the
+  /// rectangle are filled by the display while a partially covered column on either edge is
+  /// sent as in `blit_region`.
+  ///
+  /// # Returns
+  /// `DisplayError::RegionOutOfBounds` if the corners are out of order or off the display, or
+  /// `DisplayError::ArgumentOutOfRange` if `gray` is above `0xF`.
+  pub fn fill_rect(&mut self, x0: u8, y0: u8, x1: u8, y1: u8, gray: u8) -> Result<(),Box<dyn error::Error>> {
+    use commands::*;
+
+    let (x0, y0, x1, y1) = validate_rect(x0, y0, x1, y1, gray)?;
+    fill_rect_l4(&mut self.shadow, x0, y0, x1, y1, gray);
+
+    // Fill the columns fully covered by the rectangle using graphic acceleration.
+    let first_full_column = x0.div_ceil(2);
+    let full_column_end = x1.div_ceil(2);
+    if first_full_column < full_column_end {
+      self.set_graphic_acceleration(GFXACCEL_FILL)?;
+      let sequence = [
+        DRAWRECT, first_full_column as u8, y0 as u8, (full_column_end - 1) as u8, y1 as u8, gray * 0x11,
+      ];
+      self.write_sequence(DisplayMode::Command, &sequence)?;
+    }
+
+    // Send partially covered edge columns from the copy of display RAM.
+    if x0 & 1 == 1 {
+      self.write_shadow_window(x0 / 2, x0 / 2, y0, y1)?;
+    }
+    if x1 & 1 == 0 {
+      self.write_shadow_window(x1 / 2, x1 / 2, y0, y1)?;
     }
 
     Ok(())
   }
 
+  /// Draws a one pixel wide outline of the rectangle with corners (`x0`, `y0`) and (`x1`, `y1`)
+  /// inclusive in the gray level `gray`, from `0x0` to `0xF`, using the Draw Rectangle graphic
+  /// acceleration command with fill disabled.
+  ///
+  /// The display draws the left and right edges two pixels wide, so the edge columns are
+  /// restored from the driver's copy of display RAM as in `blit_region`.
+  ///
+  /// # Returns
+  /// `DisplayError::RegionOutOfBounds` if the corners are out of order or off the display, or
+  /// `DisplayError::ArgumentOutOfRange` if `gray` is above `0xF`.
+  pub fn draw_rect_outline(&mut self, x0: u8, y0: u8, x1: u8, y1: u8, gray: u8) -> Result<(),Box<dyn error::Error>> {
+    use commands::*;
+
+    let (x0, y0, x1, y1) = validate_rect(x0, y0, x1, y1, gray)?;
+    fill_rect_l4(&mut self.shadow, x0, y0, x1, y0, gray);
+    fill_rect_l4(&mut self.shadow, x0, y1, x1, y1, gray);
+    fill_rect_l4(&mut self.shadow, x0, y0, x0, y1, gray);
+    fill_rect_l4(&mut self.shadow, x1, y0, x1, y1, gray);
+
+    // Draw the outline using graphic acceleration.
+    self.set_graphic_acceleration(self.gfx_accel & !GFXACCEL_FILL)?;
+    let sequence = [
+      DRAWRECT, (x0 / 2) as u8, y0 as u8, (x1 / 2) as u8, y1 as u8, gray * 0x11,
+    ];
+    self.write_sequence(DisplayMode::Command, &sequence)?;
+
+    // Restore the neighbours of the left and right edges.
+    self.write_shadow_window(x0 / 2, x0 / 2, y0, y1)?;
+    if x1 / 2 != x0 / 2 {
+      self.write_shadow_window(x1 / 2, x1 / 2, y0, y1)?;
+    }
+
+    Ok(())
+  }
+
+  /// Copies the region of display RAM within `source` so that its top-left corner is placed at
+  /// `destination`, using the Copy graphic acceleration command. No pixel data is sent.
+  ///
+  /// The display copies whole columns of two pixels, so `source` must start on an even column
+  /// and end on an odd column, and `destination` must be on an even column. Unless
+  /// `options.wrap_x` is set, the copied region must fit on the display.
+  ///
+  /// # Returns
+  /// `DisplayError::UnalignedRegion` if the columns are not aligned to pixel pairs, or
+  /// `DisplayError::RegionOutOfBounds` if either region does not fit on the display.
+  pub fn copy_region(&mut self, source: Rect, destination: Point, options: CopyOptions) -> Result<(),Box<dyn error::Error>> {
+    use commands::*;
+
+    let (x0, y0, x1, y1) = validate_rect(source.x0, source.y0, source.x1, source.y1, 0)?;
+    let (x, y) = (destination.x as usize, destination.y as usize);
+    if x0 & 1 != 0 || x1 & 1 != 1 || x & 1 != 0 {
+      return Err(Box::new(DisplayError::UnalignedRegion));
+    }
+    let (width, height) = (x1 - x0 + 1, y1 - y0 + 1);
+    if x >= 128 || y + height > 64 || (!options.wrap_x && x + width > 128) {
+      return Err(Box::new(DisplayError::RegionOutOfBounds));
+    }
+
+    // Configure copying while preserving the fill option.
+    let mut accel = self.gfx_accel & GFXACCEL_FILL;
+    if options.reverse {
+      accel |= GFXACCEL_REVERSE;
+    }
+    if options.wrap_x {
+      accel |= GFXACCEL_WRAP;
+    }
+    self.set_graphic_acceleration(accel)?;
+
+    let sequence = [
+      COPY, (x0 / 2) as u8, y0 as u8, (x1 / 2) as u8, y1 as u8, (x / 2) as u8, y as u8,
+    ];
+    self.write_sequence(DisplayMode::Command, &sequence)?;
+    copy_columns_l4(&mut self.shadow, (x0 / 2, y0), (x1 / 2, y1), (x / 2, y), options);
+
+    Ok(())
+  }
+
+  /// Configures a horizontal scroll of the rows `first_row...last_row` of display RAM by
+  /// `columns_per_step` columns of two pixels every `interval`. Scrolling must then be started
+  /// with `set_scroll_active`, and should be stopped before it is reconfigured.
+  ///
+  /// Scrolling moves the contents of display RAM, so any region blit, fill or copy made after
+  /// scrolling should be preceded by a full frame blit or `clear()`.
+  ///
+  /// # Returns
+  /// `DisplayError::RegionOutOfBounds` if the rows are out of order or beyond the 80 rows of
+  /// display RAM, or `DisplayError::ArgumentOutOfRange` if `columns_per_step` exceeds 63.
+  pub fn set_horizontal_scroll(&mut self, first_row: u8, last_row: u8, columns_per_step: u8, interval: ScrollInterval) -> Result<(),Box<dyn error::Error>> {
+    use commands::*;
+
+    if first_row > last_row || last_row >= RAM_ROWS {
+      return Err(Box::new(DisplayError::RegionOutOfBounds));
+    }
+    if columns_per_step > 0x3F {
+      return Err(Box::new(DisplayError::ArgumentOutOfRange));
+    }
+
+    let interval = match interval {
+      ScrollInterval::Frames6 => 0x00,
+      ScrollInterval::Frames10 => 0x01,
+      ScrollInterval::Frames100 => 0x02,
+      ScrollInterval::Frames200 => 0x03,
+    };
+    let sequence = [
+      SETUPHSCROLL, columns_per_step, first_row, last_row - first_row + 1, interval,
+    ];
+    self.write_sequence(DisplayMode::Command, &sequence)
+  }
+
+  /// Start or stop the horizontal scroll configured with `set_horizontal_scroll`.
+  /// Scrolling is stopped after initialization.
+  pub fn set_scroll_active(&mut self, active: bool) -> Result<(),Box<dyn error::Error>> {
+    match active {
+      true =>
+        self.write_sequence(DisplayMode::Command, &[commands::ACTIVATESCROLL]),
+      false =>
+        self.write_sequence(DisplayMode::Command, &[commands::DEACTIVATESCROLL]),
+    }
+  }
+
+  /// Scrolls the display vertically so that the row `line` of display RAM is shown on the top
+  /// row of the panel, using a single Set Display Start Line command.
+  ///
+  /// Display RAM holds 80 rows, of which as many as the configured multiplex ratio are shown,
+  /// wrapping around after row 79. With the default ratio of 64, the panel shows the rows
+  /// `line...line + 63`. The display offset is left unchanged, as the Adafruit module relies on
+  /// it to align the first row with the top of the glass. Configured to the start line of the
+  /// display configuration after initialization.
+  ///
+  /// The multiplex ratio only limits how many rows are scanned from the start line. The start
+  /// line itself addresses display RAM, and the display wraps the scanned rows around all 80
+  /// rows of RAM whatever the ratio. Wrapping modulo the ratio instead would leave the
+  /// `80 - ratio` rows beyond the panel unreachable, and those rows are what make a scrolled
+  /// view cost a single command per step.
+  ///
+  /// # Returns
+  /// `DisplayError::ArgumentOutOfRange` if `line` is not a row of display RAM.
+  pub fn set_vertical_scroll(&mut self, line: u8) -> Result<(),Box<dyn error::Error>> {
+    if line >= RAM_ROWS {
+      return Err(Box::new(DisplayError::ArgumentOutOfRange));
+    }
+    self.write_sequence(DisplayMode::Command, &[commands::SETSTARTLINE, line])?;
+    self.start_line = line;
+    Ok(())
+  }
+
+  /// Scrolls the display vertically one row at a time from the current position until row
+  /// `line` of display RAM is at the top of the panel, waiting `step_delay` after each step.
+  /// Scrolling takes the shorter direction, wrapping around the 80 rows of display RAM.
+  ///
+  /// # Returns
+  /// `DisplayError::ArgumentOutOfRange` if `line` is not a row of display RAM.
+  pub fn smooth_scroll_to(&mut self, line: u8, step_delay: time::Duration) -> Result<(),Box<dyn error::Error>> {
+    if line >= RAM_ROWS {
+      return Err(Box::new(DisplayError::ArgumentOutOfRange));
+    }
+    while self.start_line != line {
+      let next = next_scroll_line(self.start_line, line);
+      self.set_vertical_scroll(next)?;
+      thread::sleep(step_delay);
+    }
+    Ok(())
+  }
+
+  /// Sends the graphic acceleration `options`, a combination of the `GFXACCEL_*` bits, if they
+  /// differ from the options last sent.
+  fn set_graphic_acceleration(&mut self, options: u8) -> Result<(),Box<dyn error::Error>> {
+    if self.gfx_accel != options {
+      self.write_sequence(DisplayMode::Command, &[commands::GFXACCEL, options])?;
+      self.gfx_accel = options;
+    }
+    Ok(())
+  }
+
+  /// Sends the columns `col_start...col_end` of the rows `row_start...row_end` of the copy of
+  /// display RAM to the display, restricting the display RAM address window accordingly.
+  fn write_shadow_window(&mut self, col_start: usize, col_end: usize, row_start: usize, row_end: usize) -> Result<(),Box<dyn error::Error>> {
+    use commands::*;
+
+    let preamble = [
+      SETCOLADDR, col_start as u8, col_end as u8,
+      SETROWADDR, row_start as u8, row_end as u8,
+    ];
+    self.write_sequence(DisplayMode::Command, &preamble)?;
+
+    // Send the affected bytes of each line in Data mode.
+    for row in row_start ..= row_end {
+      let line = self.shadow[row];
+      self.write_sequence(DisplayMode::Data, &line[col_start ..= col_end])?;
+    }
+
+    Ok(())
+  }
+
+  /// Sets the display RAM address window to cover the entire 128x64 display.
+  ///
+  /// # Returns
+  /// A local error if not all data could be sent.
+  fn write_full_frame_preamble(&mut self) -> Result<(),Box<dyn error::Error>> {
+    use commands::*;
+
+    // Address window covering the full display.
+    const BLIT_PREAMBLE_SEQUENCE: &[u8] = &[
+      // Set the column address range to 0x00...0x3F. Each pixel takes 4 bits.
+      SETCOLADDR, 0x00, 0x3F,
+      // Set the row address range to 0x00...0x3F. There are 64 rows.
+      SETROWADDR, 0x00, 0x3F,
+    ];
+
+    self.write_sequence(DisplayMode::Command, BLIT_PREAMBLE_SEQUENCE)
+  }
+
   /// Resets the display and waits for it to restart. Takes approximately ~550ms.
   ///
   /// # Returns
   /// An error from the control channel if the display could not enter Reset mode.
-  fn reset(&mut self) -> Result<(),Box<error::Error>> {
+  fn reset(&mut self) -> Result<(),Box<dyn error::Error>> {
     self.control_channel.run_in_mode(DisplayMode::Reset, &mut move || {
       thread::sleep(time::Duration::from_millis(10));
       Ok(())
@@ -330,12 +897,12 @@ impl<'a> Ssd1325<'a> {
   ///
   /// # Returns
   /// A local error if not all data could be sent.
-  fn write_sequence(&mut self, mode: DisplayMode, bytes: &[u8]) -> Result<(),Box<error::Error>> {
+  fn write_sequence(&mut self, mode: DisplayMode, bytes: &[u8]) -> Result<(),Box<dyn error::Error>> {
     let mut transport = &mut self.transport;
 
     // Send the sequence to the display over the transport once the control channel is configured.
     self.control_channel.run_in_mode(mode, &mut move || {
-      let sent = transport.write(bytes).map_err(|e| Box::new(e))?;
+      let sent = transport.write(bytes).map_err(Box::new)?;
       if sent < bytes.len() {
         Err(Box::new(DisplayError::WriteFailed))
       } else {
@@ -351,14 +918,88 @@ impl<'a> Ssd1325<'a> {
 /// The `unpacked` slice must be at least 4 bytes long.
 fn unpack_pixels_for_display(packed: u8, unpacked: &mut [u8]) {
   let mut pixel_group = packed;
-  for i in 0..4 {
+  for byte in unpacked.iter_mut().take(4) {
     let l = if (pixel_group & 0x80) != 0 { 0xF0 } else { 0x00 };
     let r = if (pixel_group & 0x40) != 0 { 0x0F } else { 0x00 };
-    unpacked[i] = l | r;
+    *byte = l | r;
     pixel_group <<= 2;
   }
 }
 
+/// Writes a `width` pixel wide region of gray levels with its top-left corner at (`x`, `y`)
+/// into a packed 4-bit `frame`, preserving the pixels sharing a byte with the region's edges.
+fn merge_region_l4(frame: &mut [[u8; 64]; 64], x: usize, y: usize, width: usize, pixels: &[u8]) {
+  for (row, line) in pixels.chunks(width).enumerate() {
+    for (column, &level) in line.iter().enumerate() {
+      let pixel_x = x + column;
+      let byte = &mut frame[y + row][pixel_x / 2];
+      if pixel_x & 1 == 0 {
+        *byte = (*byte & 0x0F) | ((level & 0x0F) << 4);
+      } else {
+        *byte = (*byte & 0xF0) | (level & 0x0F);
+      }
+    }
+  }
+}
+
+/// Returns the start line one step from `current` towards `target`, in the shorter direction
+/// around the rows of display RAM.
+fn next_scroll_line(current: u8, target: u8) -> u8 {
+  let forward = (target + RAM_ROWS - current) % RAM_ROWS;
+  if forward == 0 {
+    current
+  } else if forward <= RAM_ROWS / 2 {
+    (current + 1) % RAM_ROWS
+  } else {
+    (current + RAM_ROWS - 1) % RAM_ROWS
+  }
+}
+
+/// Validates the corners of a rectangle with inclusive pixel coordinates and its `gray` level.
+///
+/// # Returns
+/// The corners as `(x0, y0, x1, y1)` for indexing, or an error if they are invalid.
+fn validate_rect(x0: u8, y0: u8, x1: u8, y1: u8, gray: u8) -> Result<(usize, usize, usize, usize), DisplayError> {
+  if x0 > x1 || y0 > y1 || x1 >= 128 || y1 >= 64 {
+    return Err(DisplayError::RegionOutOfBounds);
+  }
+  if gray > 0x0F {
+    return Err(DisplayError::ArgumentOutOfRange);
+  }
+  Ok((x0 as usize, y0 as usize, x1 as usize, y1 as usize))
+}
+
+/// Fills the rectangle with corners (`x0`, `y0`) and (`x1`, `y1`) inclusive in a packed 4-bit
+/// `frame` with the gray level `gray`.
+fn fill_rect_l4(frame: &mut [[u8; 64]; 64], x0: usize, y0: usize, x1: usize, y1: usize, gray: u8) {
+  let width = x1 - x0 + 1;
+  let line = vec![gray; width];
+  for y in y0 ..= y1 {
+    merge_region_l4(frame, x0, y, width, &line);
+  }
+}
+
+/// Copies the columns between `start` and `end` inclusive, as `(column, row)` display RAM
+/// addresses, in a packed 4-bit `frame` so that `start` moves to `destination`, as performed
+/// by the Copy graphic acceleration command. The source is read in full before writing.
+fn copy_columns_l4(frame: &mut [[u8; 64]; 64], start: (usize, usize), end: (usize, usize), destination: (usize, usize), options: CopyOptions) {
+  let source = *frame;
+  for row in 0 ..= (end.1 - start.1) {
+    for column in 0 ..= (end.0 - start.0) {
+      let mut byte = source[start.1 + row][start.0 + column];
+      if options.reverse {
+        byte = !byte;
+      }
+      let target_column = destination.0 + column;
+      if target_column < 64 {
+        frame[destination.1 + row][target_column] = byte;
+      } else if options.wrap_x {
+        frame[destination.1 + row][target_column - 64] = byte;
+      }
+    }
+  }
+}
+
 /// Unpacks an entire line of pixels for display.
 fn unpack_line_for_display(line: &[u8; 16], unpacked: &mut [u8; 64]) {
   for (index, pixel) in line.iter().enumerate() {
@@ -407,4 +1048,108 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_merge_region() {
+    let mut frame = [[0x00u8; 64]; 64];
+    frame[1][0] = 0xAB;
+    frame[1][1] = 0xCD;
+
+    // An odd starting column must preserve the first nibble of the leading byte,
+    // and an odd width must preserve the second nibble of the trailing byte.
+    merge_region_l4(&mut frame, 1, 1, 2, &[0x1, 0x2]);
+    assert_eq!(frame[1][0], 0xA1);
+    assert_eq!(frame[1][1], 0x2D);
+
+    // Subsequent rows follow the region width.
+    merge_region_l4(&mut frame, 0, 2, 3, &[0x1, 0x2, 0x3, 0x4, 0x5, 0x6]);
+    assert_eq!(&frame[2][0..2], [0x12, 0x30]);
+    assert_eq!(&frame[3][0..2], [0x45, 0x60]);
+    assert_eq!(frame[4], [0x00; 64]);
+  }
+
+  #[test]
+  fn test_validate_rect() {
+    assert_eq!(validate_rect(0, 0, 127, 63, 0xF), Ok((0, 0, 127, 63)));
+    assert_eq!(validate_rect(5, 5, 5, 5, 0x0), Ok((5, 5, 5, 5)));
+    assert_eq!(validate_rect(6, 0, 5, 0, 0x0), Err(DisplayError::RegionOutOfBounds));
+    assert_eq!(validate_rect(0, 6, 0, 5, 0x0), Err(DisplayError::RegionOutOfBounds));
+    assert_eq!(validate_rect(0, 0, 128, 0, 0x0), Err(DisplayError::RegionOutOfBounds));
+    assert_eq!(validate_rect(0, 0, 0, 64, 0x0), Err(DisplayError::RegionOutOfBounds));
+    assert_eq!(validate_rect(0, 0, 0, 0, 0x10), Err(DisplayError::ArgumentOutOfRange));
+  }
+
+  #[test]
+  fn test_fill_rect() {
+    let mut frame = [[0x00u8; 64]; 64];
+    fill_rect_l4(&mut frame, 1, 2, 4, 3, 0xA);
+    assert_eq!(&frame[2][0..4], [0x0A, 0xAA, 0xA0, 0x00]);
+    assert_eq!(&frame[3][0..4], [0x0A, 0xAA, 0xA0, 0x00]);
+    assert_eq!(frame[1], [0x00; 64]);
+    assert_eq!(frame[4], [0x00; 64]);
+  }
+
+  #[test]
+  fn test_next_scroll_line() {
+    assert_eq!(next_scroll_line(0, 0), 0);
+    assert_eq!(next_scroll_line(0, 10), 1);
+    assert_eq!(next_scroll_line(10, 0), 9);
+    assert_eq!(next_scroll_line(78, 2), 79);
+    assert_eq!(next_scroll_line(79, 2), 0);
+    assert_eq!(next_scroll_line(2, 78), 1);
+    assert_eq!(next_scroll_line(0, 79), 79);
+  }
+
+  #[test]
+  fn test_copy_columns() {
+    let mut frame = [[0x00u8; 64]; 64];
+    frame[0][0] = 0x12;
+    frame[0][1] = 0x34;
+    frame[1][0] = 0x56;
+
+    // A plain copy moves bytes unchanged and leaves the source intact.
+    copy_columns_l4(&mut frame, (0, 0), (1, 1), (2, 2), CopyOptions::default());
+    assert_eq!(&frame[2][0..4], [0x00, 0x00, 0x12, 0x34]);
+    assert_eq!(&frame[3][0..4], [0x00, 0x00, 0x56, 0x00]);
+    assert_eq!(&frame[0][0..2], [0x12, 0x34]);
+
+    // Overlapping copies read the source before writing.
+    copy_columns_l4(&mut frame, (0, 0), (1, 0), (1, 0), CopyOptions::default());
+    assert_eq!(&frame[0][0..3], [0x12, 0x12, 0x34]);
+
+    // Reversed copies invert each gray level.
+    copy_columns_l4(&mut frame, (0, 1), (0, 1), (0, 4), CopyOptions { reverse: true, wrap_x: false });
+    assert_eq!(frame[4][0], 0xA9);
+
+    // Columns past the right edge are dropped unless wrapping is enabled.
+    copy_columns_l4(&mut frame, (0, 0), (1, 0), (63, 5), CopyOptions::default());
+    assert_eq!(frame[5][63], 0x12);
+    assert_eq!(frame[5][0], 0x00);
+    copy_columns_l4(&mut frame, (0, 0), (1, 0), (63, 6), CopyOptions { reverse: false, wrap_x: true });
+    assert_eq!(frame[6][63], 0x12);
+    assert_eq!(frame[6][0], 0x12);
+  }
+
+  /// Control channel which runs every command without side-band signalling.
+  struct NullControlChannel;
+
+  impl ControlChannel for NullControlChannel {
+    fn run_in_mode(&mut self, _mode: DisplayMode, f: &mut dyn FnMut() -> Result<(),Box<dyn error::Error>>) -> Result<(),Box<dyn error::Error>> {
+      f()
+    }
+  }
+
+  #[test]
+  fn test_gray_table_is_kept_in_config() {
+    let (mut transport, mut control) = (io::sink(), NullControlChannel);
+    let mut display = Ssd1325::new(&mut transport, &mut control);
+    let table = GrayTablePreset::Gamma22.table();
+    display.set_gray_table(table).unwrap();
+    display.set_orientation(Orientation::FlipH).unwrap();
+    assert_eq!(display.config.gray_table, table);
+
+    // Rejected tables leave the configuration unchanged.
+    assert!(display.set_gray_table([0x08; 8]).is_err());
+    assert_eq!(display.config.gray_table, table);
+  }
+
 }
@@ -0,0 +1,105 @@
+use config::DisplayConfig;
+
+/// Level of the Set Segment Low Voltage command documented in the SSD1325 datasheet (Table 18)
+/// for a capacitor connected between VSL and VSS, which is also the reset value.
+const VSL_CAPACITOR: u8 = 0x0E;
+
+/// Known modules built around the SSD1325, each with a vetted display configuration.
+///
+/// Only 128x64 modules are covered. Panels using all 80 rows of the display are not, as frames
+/// cover 64 rows and no such module has had its configuration checked against its datasheet.
+///
+/// Pass the configuration of a profile to `Ssd1325::init_with`, for instance
+/// `display.init_with(PanelProfile::Nhd27_12864.config())`.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum PanelProfile {
+  /// Adafruit 2.7" Monochrome 128x64 OLED Display Module. `Ssd1325::init` sends the Adafruit
+  /// reference sequence, which this profile follows apart from its VSL level.
+  Adafruit27_128x64,
+  /// Newhaven NHD-2.7-12864 modules. The color variants (such as the yellow UCY3 and white
+  /// WDW3) share the same glass and are configured identically.
+  Nhd27_12864,
+}
+
+impl PanelProfile {
+
+  /// All known profiles.
+  pub const ALL: &'static [PanelProfile] = &[
+    PanelProfile::Adafruit27_128x64,
+    PanelProfile::Nhd27_12864,
+  ];
+
+  /// Returns the profile with the given `name`, as returned by `PanelProfile::name`.
+  /// Names are matched case-insensitively.
+  pub fn from_name(name: &str) -> Option<PanelProfile> {
+    PanelProfile::ALL.iter()
+      .find(|profile| profile.name().eq_ignore_ascii_case(name))
+      .cloned()
+  }
+
+  /// Short name identifying the profile, for selection from configuration files.
+  pub fn name(&self) -> &'static str {
+    match *self {
+      PanelProfile::Adafruit27_128x64 => "adafruit-2.7-128x64",
+      PanelProfile::Nhd27_12864 => "nhd-2.7-12864",
+    }
+  }
+
+  /// Width of the panel in pixels.
+  pub fn width(&self) -> u8 {
+    128
+  }
+
+  /// Height of the panel in pixels.
+  pub fn height(&self) -> u8 {
+    64
+  }
+
+  /// Display configuration for the panel.
+  pub fn config(&self) -> DisplayConfig {
+    match *self {
+      // Matches the Adafruit reference driver, except for its VSL level of `0x0D | 0x02`,
+      // which the driver itself marks as possibly wrong and the datasheet does not document.
+      PanelProfile::Adafruit27_128x64 =>
+        DisplayConfig {
+          vsl: VSL_CAPACITOR,
+          ..DisplayConfig::default()
+        },
+      // The glass is the same as the Adafruit module. The clock setting of `0x91` follows the
+      // example initialization in the Newhaven NHD-2.7-12864UCY3 datasheet.
+      PanelProfile::Nhd27_12864 =>
+        DisplayConfig {
+          oscillator_frequency: 0x9,
+          clock_divide_ratio: 2,
+          vsl: VSL_CAPACITOR,
+          ..DisplayConfig::default()
+        },
+    }
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_profiles_are_valid() {
+    for profile in PanelProfile::ALL.iter() {
+      let config = profile.config();
+      assert_eq!(config.validate(), Ok(()), "{:?}", profile);
+      assert_eq!(config.multiplex_ratio(), profile.height());
+      assert_eq!(config.vsl, VSL_CAPACITOR);
+    }
+  }
+
+  #[test]
+  fn test_from_name() {
+    for profile in PanelProfile::ALL.iter() {
+      assert_eq!(PanelProfile::from_name(profile.name()), Some(*profile));
+    }
+    assert_eq!(PanelProfile::from_name("NHD-2.7-12864"), Some(PanelProfile::Nhd27_12864));
+    assert_eq!(PanelProfile::from_name("unknown"), None);
+  }
+
+}
@@ -0,0 +1,104 @@
+/// Rotation of a 64x128 portrait image onto the 128x64 panel.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum PortraitRotation {
+  /// The image is rotated 90 degrees clockwise, so its top edge is on the right of the panel.
+  Rotate90,
+  /// The image is rotated 270 degrees clockwise, so its top edge is on the left of the panel.
+  Rotate270,
+}
+
+impl PortraitRotation {
+  /// Returns the panel coordinates `(x, y)` of the portrait pixel at (`x`, `y`).
+  fn panel_coordinates(&self, x: usize, y: usize) -> (usize, usize) {
+    match *self {
+      PortraitRotation::Rotate90 => (127 - y, x),
+      PortraitRotation::Rotate270 => (y, 63 - x),
+    }
+  }
+}
+
+/// Transposes a 1-bit portrait `frame` of 128 rows of 64 pixels, packed 8 per byte with the
+/// most significant bit first, into the 64 rows of 128 pixels accepted by `Ssd1325::blit_l1`.
+pub fn rotate_l1(frame: &[[u8; 8]; 128], rotation: PortraitRotation) -> [[u8; 16]; 64] {
+  let mut rotated = [[0u8; 16]; 64];
+  for (y, line) in frame.iter().enumerate() {
+    for x in 0 .. 64 {
+      if (line[x / 8] & (0x80 >> (x % 8))) != 0 {
+        let (panel_x, panel_y) = rotation.panel_coordinates(x, y);
+        rotated[panel_y][panel_x / 8] |= 0x80 >> (panel_x % 8);
+      }
+    }
+  }
+  rotated
+}
+
+/// Transposes a 4-bit portrait `frame` of 128 rows of 64 pixels, packed 2 per byte with the
+/// most significant nibble first, into the 64 rows of 128 pixels accepted by `Ssd1325::blit_l4`.
+pub fn rotate_l4(frame: &[[u8; 32]; 128], rotation: PortraitRotation) -> [[u8; 64]; 64] {
+  let mut rotated = [[0u8; 64]; 64];
+  for (y, line) in frame.iter().enumerate() {
+    for x in 0 .. 64 {
+      let shift = if x & 1 == 0 { 4 } else { 0 };
+      let level = (line[x / 2] >> shift) & 0x0F;
+      let (panel_x, panel_y) = rotation.panel_coordinates(x, y);
+      let panel_shift = if panel_x & 1 == 0 { 4 } else { 0 };
+      rotated[panel_y][panel_x / 2] |= level << panel_shift;
+    }
+  }
+  rotated
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rotate_l1() {
+    // Mark the top-left corner and the pixel to its right.
+    let mut frame = [[0u8; 8]; 128];
+    frame[0][0] = 0b1100_0000;
+
+    // Rotating clockwise, the top edge of the image is along the right edge of the panel.
+    let rotated = rotate_l1(&frame, PortraitRotation::Rotate90);
+    assert_eq!(rotated[0][15], 0b0000_0001);
+    assert_eq!(rotated[1][15], 0b0000_0001);
+    assert_eq!(rotated.iter().flat_map(|line| line.iter()).filter(|&&byte| byte != 0).count(), 2);
+
+    // Rotating counter-clockwise, the top edge of the image is along the left edge of the panel.
+    let rotated = rotate_l1(&frame, PortraitRotation::Rotate270);
+    assert_eq!(rotated[63][0], 0b1000_0000);
+    assert_eq!(rotated[62][0], 0b1000_0000);
+    assert_eq!(rotated.iter().flat_map(|line| line.iter()).filter(|&&byte| byte != 0).count(), 2);
+  }
+
+  #[test]
+  fn test_rotate_l4() {
+    // Mark the bottom-right corner and the pixel above it.
+    let mut frame = [[0u8; 32]; 128];
+    frame[127][31] = 0x0A;
+    frame[126][31] = 0x05;
+
+    let rotated = rotate_l4(&frame, PortraitRotation::Rotate90);
+    assert_eq!(rotated[63][0], 0xA5);
+
+    let rotated = rotate_l4(&frame, PortraitRotation::Rotate270);
+    assert_eq!(rotated[0][63], 0x5A);
+  }
+
+  #[test]
+  fn test_opposite_rotations_differ_by_half_turn() {
+    // Rotating by 90 and 270 degrees yields images rotated by 180 degrees from each other.
+    let mut frame = [[0u8; 8]; 128];
+    for (y, line) in frame.iter_mut().enumerate() {
+      line[y % 8] = y as u8;
+    }
+    let clockwise = rotate_l1(&frame, PortraitRotation::Rotate90);
+    let counter_clockwise = rotate_l1(&frame, PortraitRotation::Rotate270);
+    for y in 0 .. 64 {
+      for x in 0 .. 16 {
+        assert_eq!(clockwise[y][x], counter_clockwise[63 - y][15 - x].reverse_bits());
+      }
+    }
+  }
+
+}
@@ -0,0 +1,209 @@
+/// Strategy used to reduce 8-bit luminance to the 16 gray levels supported by the display.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum Quantization {
+  /// Each pixel is mapped to the nearest gray level. Fastest, but bands on gradients.
+  Nearest,
+  /// Ordered dithering using a 4x4 Bayer threshold matrix. Stable between frames.
+  OrderedBayer,
+  /// Floyd-Steinberg error diffusion. Best reproduction of photographic content.
+  FloydSteinberg,
+  /// Atkinson error diffusion. Diffuses only 3/4 of the error, preserving contrast.
+  Atkinson,
+}
+
+/// Difference in 8-bit luminance between two adjacent display gray levels.
+const LEVEL_STEP: i32 = 17;
+
+/// 4x4 Bayer threshold matrix with values from 0 to 15.
+const BAYER_4X4: [[i32; 4]; 4] = [
+  [ 0,  8,  2, 10],
+  [12,  4, 14,  6],
+  [ 3, 11,  1,  9],
+  [15,  7, 13,  5],
+];
+
+/// Floyd-Steinberg diffusion weights as `(dx, dy, weight)`, out of 16.
+const FLOYD_STEINBERG: &[(isize, usize, i32)] = &[
+  (1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1),
+];
+
+/// Atkinson diffusion weights as `(dx, dy, weight)`, out of 8.
+const ATKINSON: &[(isize, usize, i32)] = &[
+  (1, 0, 1), (2, 0, 1), (-1, 1, 1), (0, 1, 1), (1, 1, 1), (0, 2, 1),
+];
+
+/// Quantizes a 128x64 8-bit luminance `frame` into the packed 4-bit format accepted by
+/// `Ssd1325::blit_l4` using the specified `quantization` strategy.
+pub fn quantize_l8(frame: &[[u8; 128]; 64], quantization: Quantization) -> [[u8; 64]; 64] {
+  let mut levels = [[0u8; 128]; 64];
+
+  match quantization {
+    Quantization::Nearest => {
+      for (y, line) in frame.iter().enumerate() {
+        for (x, &value) in line.iter().enumerate() {
+          levels[y][x] = nearest_level(i32::from(value));
+        }
+      }
+    }
+    Quantization::OrderedBayer => {
+      for (y, line) in frame.iter().enumerate() {
+        for (x, &value) in line.iter().enumerate() {
+          levels[y][x] = ordered_level(value, BAYER_4X4[y % 4][x % 4]);
+        }
+      }
+    }
+    Quantization::FloydSteinberg =>
+      diffuse_error(frame, &mut levels, FLOYD_STEINBERG, 16),
+    Quantization::Atkinson =>
+      diffuse_error(frame, &mut levels, ATKINSON, 8),
+  }
+
+  let mut packed = [[0u8; 64]; 64];
+  for (line, packed_line) in levels.iter().zip(packed.iter_mut()) {
+    pack_line_l4(line, packed_line);
+  }
+  packed
+}
+
+/// Returns the gray level from `0` to `0xF` nearest to the 8-bit luminance `value`.
+/// Values outside of `0...255` are clamped.
+fn nearest_level(value: i32) -> u8 {
+  let clamped = value.clamp(0, 255);
+  ((clamped * 15 + 127) / 255) as u8
+}
+
+/// Returns the gray level for `value` biased by a Bayer `threshold` from `0` to `15`.
+fn ordered_level(value: u8, threshold: i32) -> u8 {
+  // Computes `floor(value * 15 / 255 + (threshold + 0.5) / 16)` in integer arithmetic.
+  let numerator = i32::from(value) * 15 * 32 + (2 * threshold + 1) * 255;
+  (numerator / (255 * 32)) as u8
+}
+
+/// Quantizes `frame` into `levels`, spreading the quantization error of each pixel onto its
+/// unvisited neighbours according to `weights`, each of which is a fraction of `divisor`.
+fn diffuse_error(frame: &[[u8; 128]; 64], levels: &mut [[u8; 128]; 64], weights: &[(isize, usize, i32)], divisor: i32) {
+  let mut working: Vec<[i32; 128]> = frame.iter()
+    .map(|line| {
+      let mut values = [0i32; 128];
+      for (value, &pixel) in values.iter_mut().zip(line.iter()) {
+        *value = i32::from(pixel);
+      }
+      values
+    })
+    .collect();
+
+  for y in 0 .. 64 {
+    for x in 0 .. 128 {
+      let value = working[y][x];
+      let level = nearest_level(value);
+      let error = value - i32::from(level) * LEVEL_STEP;
+      levels[y][x] = level;
+
+      for &(dx, dy, weight) in weights {
+        let target_x = x as isize + dx;
+        let target_y = y + dy;
+        if !(0 .. 128).contains(&target_x) || target_y >= 64 {
+          continue;
+        }
+        working[target_y][target_x as usize] += error * weight / divisor;
+      }
+    }
+  }
+}
+
+/// Packs a line of 128 gray levels into 64 bytes, two pixels per byte, first pixel in the
+/// most significant nibble.
+pub(crate) fn pack_line_l4(levels: &[u8; 128], packed: &mut [u8; 64]) {
+  for (byte, pair) in packed.iter_mut().zip(levels.chunks(2)) {
+    *byte = ((pair[0] & 0x0F) << 4) | (pair[1] & 0x0F);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Returns the sum of all gray levels in a packed 4-bit frame.
+  fn total_level(frame: &[[u8; 64]; 64]) -> u32 {
+    frame.iter()
+      .flat_map(|line| line.iter())
+      .map(|&byte| u32::from(byte >> 4) + u32::from(byte & 0x0F))
+      .sum()
+  }
+
+  #[test]
+  fn test_nearest_level() {
+    assert_eq!(nearest_level(-20), 0x0);
+    assert_eq!(nearest_level(0), 0x0);
+    assert_eq!(nearest_level(8), 0x0);
+    assert_eq!(nearest_level(9), 0x1);
+    assert_eq!(nearest_level(17), 0x1);
+    assert_eq!(nearest_level(128), 0x8);
+    assert_eq!(nearest_level(255), 0xF);
+    assert_eq!(nearest_level(300), 0xF);
+  }
+
+  #[test]
+  fn test_extremes_are_preserved() {
+    let strategies = [
+      Quantization::Nearest,
+      Quantization::OrderedBayer,
+      Quantization::FloydSteinberg,
+      Quantization::Atkinson,
+    ];
+    for &strategy in strategies.iter() {
+      assert_eq!(quantize_l8(&[[0x00; 128]; 64], strategy), [[0x00; 64]; 64]);
+      assert_eq!(quantize_l8(&[[0xFF; 128]; 64], strategy), [[0xFF; 64]; 64]);
+    }
+  }
+
+  #[test]
+  fn test_nearest_packing() {
+    let mut frame = [[0u8; 128]; 64];
+    frame[0][0] = 0xFF;
+    frame[0][1] = 0x11;
+    frame[0][127] = 0x88;
+    let result = quantize_l8(&frame, Quantization::Nearest);
+    assert_eq!(result[0][0], 0xF1);
+    assert_eq!(result[0][63], 0x08);
+    assert_eq!(result[1], [0x00; 64]);
+  }
+
+  #[test]
+  fn test_dithering_preserves_average_luminance() {
+    // Roughly halfway between levels 7 and 8: nearest rounds everything down to 7,
+    // while dithering mixes both levels to approximate the original.
+    let frame = [[0x7F; 128]; 64];
+    let pixel_count = 128 * 64;
+    let expected = 0x7F as f64 / LEVEL_STEP as f64;
+
+    let nearest = total_level(&quantize_l8(&frame, Quantization::Nearest));
+    assert_eq!(nearest, 7 * pixel_count);
+
+    for &strategy in [Quantization::OrderedBayer, Quantization::FloydSteinberg].iter() {
+      let average = total_level(&quantize_l8(&frame, strategy)) as f64 / pixel_count as f64;
+      assert!((average - expected).abs() < 0.1, "{:?} average was {}", strategy, average);
+    }
+
+    // Atkinson discards a quarter of the error, so it only needs to stay between the levels.
+    let average = total_level(&quantize_l8(&frame, Quantization::Atkinson)) as f64 / pixel_count as f64;
+    assert!((7.0 ..= 8.0).contains(&average));
+  }
+
+  #[test]
+  fn test_ordered_bayer_pattern() {
+    let frame = [[0x7F; 128]; 64];
+    let result = quantize_l8(&frame, Quantization::OrderedBayer);
+    // Each 4x4 cell covers all 16 thresholds, so the pattern repeats every 4 pixels.
+    assert_eq!(result[0][0..2], result[4][2..4]);
+    // A cell mixes the two levels adjacent to the input luminance.
+    let mut cell_levels: Vec<u8> = result[0..4].iter()
+      .flat_map(|line| line[0..2].iter())
+      .flat_map(|&byte| vec![byte >> 4, byte & 0x0F])
+      .collect();
+    cell_levels.sort();
+    cell_levels.dedup();
+    assert_eq!(cell_levels, vec![7, 8]);
+  }
+
+}
@@ -0,0 +1,132 @@
+use std::{env, error, fs, path};
+
+use image::Image;
+use {HEIGHT, WIDTH};
+
+/// Environment variable which, when set, makes golden-image assertions replace the reference
+/// image with the rendered panel instead of comparing against it.
+const UPDATE_GOLDEN_VAR: &str = "SSD1325_UPDATE_GOLDEN";
+
+/// Returns `image` encoded as a binary PGM file, with a maximum value of `255`.
+pub(crate) fn encode_pgm(image: &Image) -> Vec<u8> {
+  let mut data = format!("P5\n{} {}\n255\n", image.width(), image.height()).into_bytes();
+  for y in 0 .. image.height() {
+    data.extend((0 .. image.width()).map(|x| image.pixel(x, y).unwrap_or(0)));
+  }
+  data
+}
+
+/// Returns the 64 rows of 128 pixels of a panel as an image.
+pub(crate) fn panel_image(panel: &[[u8; 128]; 64]) -> Image {
+  let pixels = panel.iter().flat_map(|line| line.iter().cloned()).collect();
+  Image::new(WIDTH, HEIGHT, pixels).expect("panel image size")
+}
+
+/// Returns the pixels of `reference` as 64 rows of 128 pixels.
+///
+/// # Returns
+/// A description of the problem if `reference` is not 128x64 pixels.
+fn reference_image(reference: &Image) -> Result<[[u8; 128]; 64], String> {
+  if (reference.width(), reference.height()) != (WIDTH, HEIGHT) {
+    return Err(format!("golden image is {}x{}, expected {}x{}", reference.width(), reference.height(), WIDTH, HEIGHT));
+  }
+  let mut image = [[0u8; 128]; 64];
+  for (y, line) in image.iter_mut().enumerate() {
+    for (x, pixel) in line.iter_mut().enumerate() {
+      *pixel = reference.pixel(x, y).unwrap_or(0);
+    }
+  }
+  Ok(image)
+}
+
+/// Returns a visual diff of `expected` and `actual`: the two images side by side, followed by
+/// `expected` dimmed to a quarter of its brightness with every differing pixel shown white.
+pub(crate) fn diff_image(expected: &[[u8; 128]; 64], actual: &[[u8; 128]; 64]) -> Image {
+  let mut pixels = Vec::with_capacity(3 * WIDTH * HEIGHT);
+  for (expected, actual) in expected.iter().zip(actual.iter()) {
+    pixels.extend_from_slice(expected);
+    pixels.extend_from_slice(actual);
+    pixels.extend(expected.iter().zip(actual.iter()).map(|(&expected, &actual)| {
+      if expected == actual { expected / 4 } else { 0xFF }
+    }));
+  }
+  Image::new(3 * WIDTH, HEIGHT, pixels).expect("diff image size")
+}
+
+/// Returns the path the visual diff against the golden image at `path` is written to.
+pub(crate) fn diff_path(path: &path::Path) -> path::PathBuf {
+  path.with_extension("diff.pgm")
+}
+
+/// Compares `actual` with the golden PGM image at `path`, replacing the golden image instead if
+/// the `SSD1325_UPDATE_GOLDEN` environment variable is set. On a mismatch, a visual diff from
+/// `diff_image` is written next to the golden image.
+///
+/// # Returns
+/// A description of the mismatch, or of why the golden image could not be read or written.
+pub(crate) fn compare_golden(actual: &[[u8; 128]; 64], path: &path::Path) -> Result<(), Box<dyn error::Error>> {
+  if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+    fs::write(path, encode_pgm(&panel_image(actual)))?;
+    return Ok(());
+  }
+
+  let reference = Image::open(path).map_err(|error| {
+    format!("could not read golden image {}: {} (set {} to create it)", path.display(), error, UPDATE_GOLDEN_VAR)
+  })?;
+  let expected = reference_image(&reference)?;
+  let mismatches: Vec<(usize, usize)> = (0 .. HEIGHT)
+    .flat_map(|y| (0 .. WIDTH).map(move |x| (x, y)))
+    .filter(|&(x, y)| expected[y][x] != actual[y][x])
+    .collect();
+  if mismatches.is_empty() {
+    return Ok(());
+  }
+
+  let diff_path = diff_path(path);
+  fs::write(&diff_path, encode_pgm(&diff_image(&expected, actual)))?;
+  let (x, y) = mismatches[0];
+  Err(format!(
+    "panel differs from golden image {} at {} pixels, first at ({}, {}); visual diff written to {}",
+    path.display(), mismatches.len(), x, y, diff_path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_encode_pgm() {
+    let mut image = [[0u8; 128]; 64];
+    image[0][1] = 0x80;
+    image[63][127] = 0xFF;
+    let data = encode_pgm(&panel_image(&image));
+    assert!(data.starts_with(b"P5\n128 64\n255\n"));
+    assert_eq!(data.len(), 14 + 128 * 64);
+
+    // The encoded image decodes back to the same pixels.
+    let decoded = Image::decode(&data).unwrap();
+    assert_eq!(reference_image(&decoded), Ok(image));
+  }
+
+  #[test]
+  fn test_diff_image() {
+    let expected = [[0x40u8; 128]; 64];
+    let mut actual = expected;
+    actual[2][3] = 0x00;
+    let diff = diff_image(&expected, &actual);
+    assert_eq!((diff.width(), diff.height()), (384, 64));
+    assert_eq!(diff.pixel(3, 2), Some(0x40));
+    assert_eq!(diff.pixel(128 + 3, 2), Some(0x00));
+    assert_eq!(diff.pixel(256 + 3, 2), Some(0xFF));
+    assert_eq!(diff.pixel(256 + 4, 2), Some(0x10));
+
+    assert_eq!(diff_path(path::Path::new("golden/menu.pgm")), path::Path::new("golden/menu.diff.pgm"));
+  }
+
+  #[test]
+  fn test_reference_size() {
+    let reference = Image::new(2, 2, vec![0; 4]).unwrap();
+    assert!(reference_image(&reference).is_err());
+  }
+
+}
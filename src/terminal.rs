@@ -0,0 +1,233 @@
+use std::cell::RefCell;
+use std::fmt::Write as FmtWrite;
+use std::rc::Rc;
+use std::{error, io};
+
+use emulator::Emulator;
+use {ControlChannel, DisplayMode, HEIGHT, WIDTH};
+
+/// First color of the 24 step gray ramp of the ANSI 256-color palette, from `8` to `238`.
+const ANSI_GRAY_RAMP: u8 = 232;
+/// Black in the 6x6x6 color cube of the ANSI 256-color palette.
+const ANSI_BLACK: u8 = 16;
+/// White in the 6x6x6 color cube of the ANSI 256-color palette.
+const ANSI_WHITE: u8 = 231;
+
+/// How pixels of the panel are drawn with characters in a terminal.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum PreviewStyle {
+  /// Each character shows two pixels stacked vertically with the upper half block `▀`, in
+  /// their own shades of gray. The panel takes up 128 columns and 32 rows.
+  HalfBlock,
+  /// Each character shows a block of 2x4 pixels with a braille pattern, lit where the pixels
+  /// are on, in the shade of the brightest pixel. The panel takes up 64 columns and 16 rows.
+  Braille,
+}
+
+impl PreviewStyle {
+  /// Returns the number of terminal rows taken up by the panel.
+  fn rows(&self) -> usize {
+    match *self {
+      PreviewStyle::HalfBlock => HEIGHT / 2,
+      PreviewStyle::Braille => HEIGHT / 4,
+    }
+  }
+}
+
+/// Returns the color of the ANSI 256-color palette closest to the gray `brightness`.
+fn ansi_gray(brightness: u8) -> u8 {
+  // The ramp runs from 8 to 238 in steps of 10, between black and white.
+  match brightness {
+    0 ..= 3 => ANSI_BLACK,
+    247 ..= 255 => ANSI_WHITE,
+    _ => ANSI_GRAY_RAMP + ((brightness.max(8) - 8 + 5) / 10).min(23),
+  }
+}
+
+/// Returns `image`, the brightness of each pixel of the panel as from
+/// `Emulator::rendered_image`, drawn with ANSI 256-color escape sequences in `style`. Each
+/// line ends with the attributes reset and a newline.
+pub fn render_ansi(image: &[[u8; 128]; 64], style: PreviewStyle) -> String {
+  let mut text = String::new();
+  match style {
+    PreviewStyle::HalfBlock => {
+      for rows in image.chunks(2) {
+        for (&top, &bottom) in rows[0].iter().zip(rows[1].iter()) {
+          let _ = write!(text, "\x1b[38;5;{};48;5;{}m\u{2580}", ansi_gray(top), ansi_gray(bottom));
+        }
+        text.push_str("\x1b[0m\n");
+      }
+    }
+    PreviewStyle::Braille => {
+      // Bit of the braille pattern for each pixel of a 2x4 block, by row then column.
+      const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+      for rows in image.chunks(4) {
+        text.push_str("\x1b[48;5;16m");
+        for x in (0 .. WIDTH).step_by(2) {
+          let (mut pattern, mut brightest) = (0, 0);
+          for (row, dots) in rows.iter().zip(DOTS.iter()) {
+            for (&pixel, &dot) in row[x .. x + 2].iter().zip(dots.iter()) {
+              if pixel != 0 {
+                pattern |= dot;
+                brightest = brightest.max(pixel);
+              }
+            }
+          }
+          let character = ::std::char::from_u32(0x2800 + pattern).unwrap_or(' ');
+          let _ = write!(text, "\x1b[38;5;{}m{}", ansi_gray(brightest), character);
+        }
+        text.push_str("\x1b[0m\n");
+      }
+    }
+  }
+  text
+}
+
+/// State of a terminal preview, shared by every handle to it.
+struct PreviewState<W> {
+  output: W,
+  style: PreviewStyle,
+  /// Image last drawn to the terminal, if any.
+  drawn: Option<[[u8; 128]; 64]>,
+}
+
+/// A live preview of an emulated display in a terminal, for developing screens without
+/// hardware.
+///
+/// Like `Emulator`, each handle to the preview is both a transport and a control channel, and
+/// handles made with `clone` share the same display. Whenever the driver returns the control
+/// channel to idle after sending commands or data, the panel is redrawn to the output in place
+/// of the previous drawing if its image has changed.
+pub struct TerminalPreview<W: io::Write> {
+  emulator: Emulator,
+  state: Rc<RefCell<PreviewState<W>>>,
+}
+
+impl<W: io::Write> TerminalPreview<W> {
+
+  /// Returns a preview of a display in its power-on state drawn to `output`, usually
+  /// `io::stdout()`, in `style`. The panel is first drawn when the driver first uses the control
+  /// channel.
+  pub fn new(output: W, style: PreviewStyle) -> Self {
+    TerminalPreview {
+      emulator: Emulator::new(),
+      state: Rc::new(RefCell::new(PreviewState { output, style, drawn: None })),
+    }
+  }
+
+  /// Returns a handle to the emulated display shown by the preview.
+  pub fn emulator(&self) -> Emulator {
+    self.emulator.clone()
+  }
+
+  /// Draws the panel to the output, even if it has not changed since it was last drawn.
+  pub fn refresh(&self) -> Result<(), io::Error> {
+    self.draw(true)
+  }
+
+  /// Draws the panel to the output in place of the previous drawing, if its image has changed
+  /// or `force` is set.
+  fn draw(&self, force: bool) -> Result<(), io::Error> {
+    let image = self.emulator.rendered_image();
+    let mut state = self.state.borrow_mut();
+    if !force && state.drawn.as_ref() == Some(&image) {
+      return Ok(());
+    }
+
+    let mut text = String::new();
+    if state.drawn.is_some() {
+      // Move the cursor back up to the start of the previous drawing.
+      let _ = write!(text, "\x1b[{}A\r", state.style.rows());
+    }
+    text.push_str(&render_ansi(&image, state.style));
+    state.output.write_all(text.as_bytes())?;
+    state.output.flush()?;
+    state.drawn = Some(image);
+    Ok(())
+  }
+
+}
+
+impl<W: io::Write> Clone for TerminalPreview<W> {
+  fn clone(&self) -> Self {
+    TerminalPreview { emulator: self.emulator.clone(), state: self.state.clone() }
+  }
+}
+
+impl<W: io::Write> io::Write for TerminalPreview<W> {
+  fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
+    self.emulator.write(data)
+  }
+
+  fn flush(&mut self) -> Result<(), io::Error> {
+    self.emulator.flush()
+  }
+}
+
+impl<W: io::Write> ControlChannel for TerminalPreview<W> {
+  fn run_in_mode(&mut self, mode: DisplayMode, f: &mut dyn FnMut() -> Result<(),Box<dyn error::Error>>) -> Result<(),Box<dyn error::Error>> {
+    self.emulator.run_in_mode(mode, f)?;
+    self.draw(false)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use commands::*;
+
+  #[test]
+  fn test_ansi_gray() {
+    assert_eq!(ansi_gray(0), ANSI_BLACK);
+    assert_eq!(ansi_gray(8), ANSI_GRAY_RAMP);
+    assert_eq!(ansi_gray(128), ANSI_GRAY_RAMP + 12);
+    assert_eq!(ansi_gray(238), ANSI_GRAY_RAMP + 23);
+    assert_eq!(ansi_gray(255), ANSI_WHITE);
+  }
+
+  #[test]
+  fn test_render_half_block() {
+    let mut image = [[0u8; 128]; 64];
+    image[0][0] = 255;
+    image[1][1] = 128;
+    let text = render_ansi(&image, PreviewStyle::HalfBlock);
+    assert_eq!(text.lines().count(), 32);
+    assert!(text.starts_with("\x1b[38;5;231;48;5;16m\u{2580}\x1b[38;5;16;48;5;244m\u{2580}"));
+    assert_eq!(text.lines().next().unwrap().matches('\u{2580}').count(), 128);
+  }
+
+  #[test]
+  fn test_render_braille() {
+    let mut image = [[0u8; 128]; 64];
+    image[0][0] = 128;
+    image[3][1] = 255;
+    let text = render_ansi(&image, PreviewStyle::Braille);
+    assert_eq!(text.lines().count(), 16);
+    assert!(text.starts_with("\x1b[48;5;16m\x1b[38;5;231m\u{2881}\x1b[38;5;16m\u{2800}"));
+  }
+
+  #[test]
+  fn test_refresh_in_place() {
+    let preview = TerminalPreview::new(Vec::new(), PreviewStyle::Braille);
+    let (mut control, mut transport) = (preview.clone(), preview.clone());
+    control.run_in_mode(DisplayMode::Command, &mut || {
+      io::Write::write_all(&mut transport, &[SETMULTIPLEX, 0x3F, SETOFFSET, 0x4C, SETREMAP, 0x50, DISPLAYON])?;
+      Ok(())
+    }).unwrap();
+    let drawn = preview.state.borrow().output.len();
+    assert!(drawn > 0);
+
+    // The panel is only drawn again once it changes, over the previous drawing.
+    control.run_in_mode(DisplayMode::Idle, &mut || Ok(())).unwrap();
+    assert_eq!(preview.state.borrow().output.len(), drawn);
+    control.run_in_mode(DisplayMode::Data, &mut || {
+      io::Write::write_all(&mut transport, &[0xF0])?;
+      Ok(())
+    }).unwrap();
+    let output = String::from_utf8(preview.state.borrow().output.clone()).unwrap();
+    assert!(output[drawn ..].starts_with("\x1b[16A\r"));
+    assert_eq!(preview.emulator().gddram()[0][0], 0xF0);
+  }
+
+}
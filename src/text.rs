@@ -0,0 +1,332 @@
+use fonts;
+use framebuffer::FrameBuffer;
+use Rect;
+
+/// The bitmap of a single character, positioned relative to the pen.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Glyph<'a> {
+  /// Width of the bitmap in pixels.
+  pub width: i32,
+  /// Height of the bitmap in pixels.
+  pub height: i32,
+  /// Horizontal distance from the pen to the left edge of the bitmap.
+  pub x_offset: i32,
+  /// Vertical distance from the top of the line to the top edge of the bitmap.
+  pub y_offset: i32,
+  /// Horizontal distance the pen moves after drawing the glyph.
+  pub advance: i32,
+  /// Rows of pixels, each `(width + 7) / 8` bytes long, packed 8 per byte with the most
+  /// significant bit first.
+  pub bitmap: &'a [u8],
+}
+
+impl<'a> Glyph<'a> {
+  /// Returns whether the pixel at (`x`, `y`) of the bitmap is set.
+  pub fn pixel(&self, x: i32, y: i32) -> bool {
+    if x < 0 || y < 0 || x >= self.width || y >= self.height {
+      return false;
+    }
+    let stride = (self.width as usize).div_ceil(8);
+    let byte = self.bitmap.get(y as usize * stride + x as usize / 8).cloned().unwrap_or(0);
+    (byte & (0x80 >> (x % 8))) != 0
+  }
+}
+
+/// A source of glyphs for drawing text.
+pub trait Font {
+  /// Distance in pixels between the tops of consecutive lines of text.
+  fn line_height(&self) -> i32;
+
+  /// Returns the glyph for `character`, or `None` if the font does not include it.
+  fn glyph(&self, character: char) -> Option<Glyph<'_>>;
+}
+
+/// Monospace bitmap fonts covering printable ASCII, included in the crate.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum BuiltinFont {
+  /// 5x7 pixel cells, fitting 25 columns and 9 lines on the display.
+  Font5x7,
+  /// 6x8 pixel cells, fitting 21 columns and 8 lines on the display.
+  Font6x8,
+  /// 8x16 pixel cells, fitting 16 columns and 4 lines on the display.
+  Font8x16,
+}
+
+impl BuiltinFont {
+  /// Width of each character cell in pixels.
+  pub fn width(&self) -> i32 {
+    match *self {
+      BuiltinFont::Font5x7 => 5,
+      BuiltinFont::Font6x8 => 6,
+      BuiltinFont::Font8x16 => 8,
+    }
+  }
+
+  /// Height of each character cell in pixels.
+  pub fn height(&self) -> i32 {
+    match *self {
+      BuiltinFont::Font5x7 => 7,
+      BuiltinFont::Font6x8 => 8,
+      BuiltinFont::Font8x16 => 16,
+    }
+  }
+}
+
+impl Font for BuiltinFont {
+  fn line_height(&self) -> i32 {
+    self.height()
+  }
+
+  fn glyph(&self, character: char) -> Option<Glyph<'_>> {
+    let code = character as u32;
+    if !(0x20 .. 0x7F).contains(&code) {
+      return None;
+    }
+    let index = (code - 0x20) as usize;
+    let bitmap: &'static [u8] = match *self {
+      BuiltinFont::Font5x7 => &fonts::FONT_5X7[index],
+      BuiltinFont::Font6x8 => &fonts::FONT_6X8[index],
+      BuiltinFont::Font8x16 => &fonts::FONT_8X16[index],
+    };
+    Some(Glyph {
+      width: self.width(),
+      height: self.height(),
+      x_offset: 0,
+      y_offset: 0,
+      advance: self.width(),
+      bitmap,
+    })
+  }
+}
+
+/// Horizontal alignment of each line of text relative to the position it is drawn at.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum Alignment {
+  /// Lines start at the position.
+  Left,
+  /// Lines are centered on the position.
+  Center,
+  /// Lines end at the position.
+  Right,
+}
+
+/// Describes how text is drawn.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct TextStyle {
+  /// Value of the pixels of each glyph. The background is left unchanged.
+  pub value: u8,
+  /// Alignment of each line relative to the position.
+  pub alignment: Alignment,
+  /// Width in pixels at which lines are wrapped, breaking between words where possible.
+  pub wrap_width: Option<i32>,
+  /// Rectangle outside of which no pixels are drawn.
+  pub clip: Option<Rect>,
+}
+
+impl TextStyle {
+  /// Returns a style drawing left-aligned text with `value`, without wrapping or clipping.
+  pub fn new(value: u8) -> Self {
+    TextStyle { value, alignment: Alignment::Left, wrap_width: None, clip: None }
+  }
+}
+
+/// Returns the glyph for `character` in `font`, falling back to `'?'` for missing characters.
+fn glyph_or_replacement<F: Font + ?Sized>(font: &F, character: char) -> Option<Glyph<'_>> {
+  font.glyph(character).or_else(|| font.glyph('?'))
+}
+
+/// Returns the width in pixels of the widest line of `text` drawn in `font`.
+pub fn text_width<F: Font + ?Sized>(font: &F, text: &str) -> i32 {
+  lines_width(&|character| advance(font, character), text)
+}
+
+/// Splits `text` into the lines it is drawn as in `font`. Lines end at each line break and,
+/// if `wrap_width` is set, before the first word which does not fit in it. Words wider than
+/// `wrap_width` are broken between characters.
+pub fn wrap_text<'a, F: Font + ?Sized>(font: &F, text: &'a str, wrap_width: Option<i32>) -> Vec<&'a str> {
+  wrap_lines(&|character| advance(font, character), text, wrap_width)
+}
+
+/// Returns the distance the pen moves after drawing `character` in `font`.
+fn advance<F: Font + ?Sized>(font: &F, character: char) -> i32 {
+  glyph_or_replacement(font, character).map_or(0, |glyph| glyph.advance)
+}
+
+/// Returns the width of the widest line of `text`, where each character moves the pen by
+/// `advance`.
+pub(crate) fn lines_width(advance: &dyn Fn(char) -> i32, text: &str) -> i32 {
+  text.lines()
+    .map(|line| line.chars().map(advance).sum())
+    .max()
+    .unwrap_or(0)
+}
+
+/// Splits `text` into lines as described by `wrap_text`, where each character moves the pen
+/// by `advance`.
+pub(crate) fn wrap_lines<'a>(advance: &dyn Fn(char) -> i32, text: &'a str, wrap_width: Option<i32>) -> Vec<&'a str> {
+  let mut lines = Vec::new();
+  for paragraph in text.split('\n') {
+    let paragraph = paragraph.trim_end_matches('\r');
+    let wrap_width = match wrap_width {
+      Some(wrap_width) => wrap_width,
+      None => {
+        lines.push(paragraph);
+        continue;
+      }
+    };
+
+    let characters: Vec<(usize, char)> = paragraph.char_indices().collect();
+    let mut start = 0;
+    let mut width = 0;
+    let mut space: Option<usize> = None;
+    let mut position = 0;
+    while position < characters.len() {
+      let (index, character) = characters[position];
+      let character_advance = advance(character);
+      if character == ' ' {
+        space = Some(index);
+      } else if width + character_advance > wrap_width && index > start {
+        // Break at the last space on the line, or before this character if there is none.
+        let (end, next) = match space {
+          Some(space) => (space, space + 1),
+          None => (index, index),
+        };
+        lines.push(paragraph[start .. end].trim_end_matches(' '));
+        start = next + (paragraph[next ..].len() - paragraph[next ..].trim_start_matches(' ').len());
+        width = lines_width(advance, &paragraph[start .. index]);
+        space = None;
+        continue;
+      }
+      width += character_advance;
+      position += 1;
+    }
+    lines.push(&paragraph[start ..]);
+  }
+  lines
+}
+
+/// Lays out `text` with the top of the first line at `y`, aligned to `x` as set by `style`,
+/// where each character moves the pen by `advance` and lines are `line_height` apart. Calls
+/// `draw` with each character and the pen position and top of its line. Returns the height
+/// of the lines laid out in pixels.
+pub(crate) fn layout_text<D: FnMut(char, i32, i32)>(advance: &dyn Fn(char) -> i32, line_height: i32, text: &str, x: i32, y: i32, style: &TextStyle, mut draw: D) -> i32 {
+  let lines = wrap_lines(advance, text, style.wrap_width);
+  for (number, line) in lines.iter().enumerate() {
+    let top = y + number as i32 * line_height;
+    let mut pen = match style.alignment {
+      Alignment::Left => x,
+      Alignment::Center => x - lines_width(advance, line) / 2,
+      Alignment::Right => x - lines_width(advance, line),
+    };
+    for character in line.chars() {
+      draw(character, pen, top);
+      pen += advance(character);
+    }
+  }
+  lines.len() as i32 * line_height
+}
+
+/// Draws `text` in `font` onto `buffer` with the top of the first line at `y`, aligned to `x`.
+/// Returns the height of the lines drawn in pixels.
+pub(crate) fn draw_text<B: FrameBuffer + ?Sized, F: Font + ?Sized>(buffer: &mut B, font: &F, text: &str, x: i32, y: i32, style: &TextStyle) -> i32 {
+  layout_text(&|character| advance(font, character), font.line_height(), text, x, y, style, |character, pen, top| {
+    if let Some(glyph) = glyph_or_replacement(font, character) {
+      for glyph_y in 0 .. glyph.height {
+        for glyph_x in 0 .. glyph.width {
+          let (pixel_x, pixel_y) = (pen + glyph.x_offset + glyph_x, top + glyph.y_offset + glyph_y);
+          if glyph.pixel(glyph_x, glyph_y) && is_within(style.clip, pixel_x, pixel_y) {
+            buffer.set_pixel(pixel_x, pixel_y, style.value);
+          }
+        }
+      }
+    }
+  })
+}
+
+/// Returns whether (`x`, `y`) is within `clip`, or `true` if there is no clip rectangle.
+pub(crate) fn is_within(clip: Option<Rect>, x: i32, y: i32) -> bool {
+  match clip {
+    Some(clip) =>
+      x >= i32::from(clip.x0) && x <= i32::from(clip.x1) &&
+      y >= i32::from(clip.y0) && y <= i32::from(clip.y1),
+    None => true,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use draw::Canvas;
+  use framebuffer::FrameBufferL4;
+
+  #[test]
+  fn test_builtin_glyphs() {
+    for &font in [BuiltinFont::Font5x7, BuiltinFont::Font6x8, BuiltinFont::Font8x16].iter() {
+      let space = font.glyph(' ').unwrap();
+      assert_eq!((space.width, space.height, space.advance), (font.width(), font.height(), font.width()));
+      assert!(space.bitmap.iter().all(|&row| row == 0));
+
+      // Every printable character other than space has pixels within its cell.
+      for code in 0x21u8 .. 0x7F {
+        let glyph = font.glyph(code as char).unwrap();
+        assert!(glyph.bitmap.iter().any(|&row| row != 0), "{:?} {:?}", font, code as char);
+        assert!(glyph.bitmap.iter().all(|&row| row & (0xFFu16 >> font.width()) as u8 == 0));
+      }
+      assert_eq!(font.glyph('\u{7F}'), None);
+      assert_eq!(font.glyph('é'), None);
+    }
+  }
+
+  #[test]
+  fn test_text_width() {
+    let font = BuiltinFont::Font6x8;
+    assert_eq!(text_width(&font, ""), 0);
+    assert_eq!(text_width(&font, "Hello"), 30);
+    assert_eq!(text_width(&font, "Hi\nthere"), 30);
+    // Missing characters are replaced with '?'.
+    assert_eq!(text_width(&font, "é"), 6);
+  }
+
+  #[test]
+  fn test_wrap_text() {
+    let font = BuiltinFont::Font6x8;
+    assert_eq!(wrap_text(&font, "one two three", None), vec!["one two three"]);
+    assert_eq!(wrap_text(&font, "one\r\ntwo", None), vec!["one", "two"]);
+    assert_eq!(wrap_text(&font, "one two three", Some(48)), vec!["one two", "three"]);
+    assert_eq!(wrap_text(&font, "one  two   three", Some(30)), vec!["one", "two", "three"]);
+    assert_eq!(wrap_text(&font, "abcdefgh ij", Some(30)), vec!["abcde", "fgh", "ij"]);
+    assert_eq!(wrap_text(&font, "", Some(30)), vec![""]);
+  }
+
+  #[test]
+  fn test_draw_text() {
+    let mut buffer = FrameBufferL4::new();
+    let height = buffer.draw_text(&BuiltinFont::Font6x8, "||", 0, 0, &TextStyle::new(0xA));
+    assert_eq!(height, 8);
+
+    // The bar of the 6x8 font is in the third column of each cell.
+    assert_eq!(buffer.get_pixel(2, 3), Some(0xA));
+    assert_eq!(buffer.get_pixel(8, 3), Some(0xA));
+    assert_eq!(buffer.get_pixel(3, 3), Some(0x0));
+
+    // Right-aligned text ends at the position.
+    let mut aligned = FrameBufferL4::new();
+    let style = TextStyle { alignment: Alignment::Right, ..TextStyle::new(0xF) };
+    aligned.draw_text(&BuiltinFont::Font6x8, "|", 128, 0, &style);
+    assert_eq!(aligned.get_pixel(124, 3), Some(0xF));
+
+    // Wrapped text continues on the following lines.
+    let mut wrapped = FrameBufferL4::new();
+    let style = TextStyle { wrap_width: Some(6), ..TextStyle::new(0xF) };
+    assert_eq!(wrapped.draw_text(&BuiltinFont::Font5x7, "| |", 0, 0, &style), 14);
+    assert_eq!(wrapped.get_pixel(2, 9), Some(0xF));
+
+    // Pixels outside of the clip rectangle are left unchanged.
+    let mut clipped = FrameBufferL4::new();
+    let style = TextStyle { clip: Some(Rect { x0: 0, y0: 0, x1: 5, y1: 63 }), ..TextStyle::new(0xF) };
+    clipped.draw_text(&BuiltinFont::Font6x8, "||", 0, 0, &style);
+    assert_eq!(clipped.get_pixel(2, 3), Some(0xF));
+    assert_eq!(clipped.get_pixel(8, 3), Some(0x0));
+  }
+
+}
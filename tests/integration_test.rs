@@ -1,10 +1,14 @@
 
+// The baseline error simulation tests compare against `true` explicitly.
+#![allow(clippy::bool_assert_comparison)]
+
 extern crate ssd1325;
 
 use std::cell::RefCell;
 use std::error;
 use std::io;
 use std::rc::Rc;
+use std::time;
 
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 enum Event {
@@ -28,7 +32,7 @@ struct MockControlChannel {
 }
 
 impl ssd1325::ControlChannel for MockControlChannel {
-  fn run_in_mode(&mut self, mode: ssd1325::DisplayMode, f: &mut FnMut() -> Result<(),Box<error::Error>>) -> Result<(),Box<error::Error>> {
+  fn run_in_mode(&mut self, mode: ssd1325::DisplayMode, f: &mut dyn FnMut() -> Result<(),Box<dyn error::Error>>) -> Result<(),Box<dyn error::Error>> {
     {
       // Log the channel entering the specified mode.
       let mut log = self.event_log.borrow_mut();
@@ -45,7 +49,7 @@ impl ssd1325::ControlChannel for MockControlChannel {
     }
 
     if self.sim_error {
-      return Err(Box::new(io::Error::new(io::ErrorKind::Other, "oh no!")));
+      return Err(Box::new(io::Error::other("oh no!")));
     }
 
     // Invoke the requested function.
@@ -74,7 +78,7 @@ impl io::Write for MockDataChannel {
     if self.sim_write_zero {
       Ok(0)
     } else if self.sim_write_error {
-      Err(io::Error::new(io::ErrorKind::Other, "oh no!"))
+      Err(io::Error::other("oh no!"))
     } else {
       self.event_log.borrow_mut().push(Event::SendData);
       Ok(data.len())
@@ -118,6 +122,31 @@ fn test_init() {
   assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
 }
 
+#[test]
+fn test_init_with() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Perform the initialization sequence with a customized configuration.
+  let config = ssd1325::DisplayConfig::builder()
+    .contrast(0x40)
+    .row_period(0x30)
+    .build()
+    .unwrap();
+  display.init_with(config).unwrap();
+
+  // Expected initialization flow is identical to the default configuration.
+  let event_log = log.borrow_mut();
+  assert_eq!(event_log.len(), 5);
+
+  let mut event_log_iter = event_log.iter();
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterReset);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterCommand);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::SendData);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+}
+
 #[test]
 fn test_clear() {
   let (ref mut control, ref mut data, ref log) = create_test_setup();
@@ -195,6 +224,84 @@ fn test_set_inverted_normal() {
   assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
 }
 
+#[test]
+fn test_set_contrast_and_current_range() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Each adjustment sends a single command.
+  display.set_contrast(0x20).unwrap();
+  display.set_current_range(ssd1325::CurrentRange::Quarter).unwrap();
+  display.set_current_range(ssd1325::CurrentRange::Half).unwrap();
+  display.set_current_range(ssd1325::CurrentRange::Full).unwrap();
+  assert_eq!(log.borrow().len(), 4 * 3);
+  log.borrow_mut().clear();
+
+  // Contrast is limited to 7 bits.
+  let error = display.set_contrast(0x80).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::ArgumentOutOfRange));
+  let error = display.fade_to(0x80, time::Duration::from_millis(0)).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::ArgumentOutOfRange));
+  assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_fade_to() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Fading sends one command per contrast step, starting from the configured maximum.
+  display.fade_to(0x70, time::Duration::from_millis(15)).unwrap();
+  assert_eq!(log.borrow().len(), 15 * 3);
+  log.borrow_mut().clear();
+
+  display.fade_to(0x78, time::Duration::from_millis(0)).unwrap();
+  assert_eq!(log.borrow().len(), 8 * 3);
+  log.borrow_mut().clear();
+
+  // Fading to the current contrast does nothing.
+  display.fade_to(0x78, time::Duration::from_millis(0)).unwrap();
+  assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_set_gray_table() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Setting a gray table sends a single command.
+  display.set_gray_table(ssd1325::GrayTablePreset::Gamma22.table()).unwrap();
+  assert_eq!(log.borrow().len(), 3);
+  log.borrow_mut().clear();
+
+  // Increments are limited to 3 bits.
+  let error = display.set_gray_table([0x08; 8]).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::ArgumentOutOfRange));
+  assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_set_orientation() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Each orientation is applied with a single command sequence.
+  display.set_orientation(ssd1325::Orientation::Rotate180).unwrap();
+  display.set_orientation(ssd1325::Orientation::FlipH).unwrap();
+  display.set_orientation(ssd1325::Orientation::FlipV).unwrap();
+  display.set_orientation(ssd1325::Orientation::Rotate0).unwrap();
+
+  let event_log = log.borrow();
+  assert_eq!(event_log.len(), 4 * 3);
+
+  let mut event_log_iter = event_log.iter();
+  for _ in 0 .. 4 {
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterCommand);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::SendData);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+  }
+}
+
 #[test]
 fn test_blit_l1() {
   let (ref mut control, ref mut data, ref log) = create_test_setup();
@@ -232,6 +339,339 @@ fn test_blit_l1() {
   }
 }
 
+#[test]
+fn test_blit_l4() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Build a horizontal gradient image test sequence to blit.
+  let mut test_sequence = [[0u8; 64]; 64];
+  for line in test_sequence.iter_mut() {
+    for (index, pixels) in line.iter_mut().enumerate() {
+      *pixels = ((index as u8) / 4) * 0x11;
+    }
+  }
+
+  // Blit the image to the screen.
+  display.blit_l4(&test_sequence).unwrap();
+
+  // Expected blit flow:
+  //  - Enter Command.
+  //  - Send Data (6).
+  //  - Enter Idle.
+  // [ 64x
+  //    - Enter Data.
+  //    - Send Data (64).
+  //    - Enter Idle.
+  // ]
+  let event_log = log.borrow_mut();
+  assert_eq!(event_log.len(), 3 + (64 * 3));
+
+  // Check the blit preamble was sent.
+  let mut event_log_iter = event_log.iter();
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterCommand);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::SendData);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+
+  // Check all 64 lines were sent.
+  for _ in 0 .. 64 {
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterData);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::SendData);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+  }
+}
+
+#[test]
+fn test_blit_l8() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Build a mid-gray image test sequence to blit.
+  let test_sequence = &[[0x80u8; 128]; 64];
+
+  // Blit the image to the screen with dithering.
+  display.blit_l8(test_sequence, ssd1325::Quantization::FloydSteinberg).unwrap();
+
+  // Expected blit flow is identical to a 4-bit blit:
+  //  - Enter Command, Send Data (6), Enter Idle.
+  //  - 64x Enter Data, Send Data (64), Enter Idle.
+  let event_log = log.borrow_mut();
+  assert_eq!(event_log.len(), 3 + (64 * 3));
+  assert_eq!(event_log[0], Event::ControlChannelEnterCommand);
+  assert_eq!(event_log[3], Event::ControlChannelEnterData);
+}
+
+#[test]
+fn test_blit_portrait() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Portrait frames are sent as a full landscape frame.
+  display.blit_l1_portrait(&[[0xAAu8; 8]; 128], ssd1325::PortraitRotation::Rotate90).unwrap();
+  assert_eq!(log.borrow().len(), 3 + (64 * 3));
+  log.borrow_mut().clear();
+
+  display.blit_l4_portrait(&[[0x5Au8; 32]; 128], ssd1325::PortraitRotation::Rotate270).unwrap();
+  assert_eq!(log.borrow().len(), 3 + (64 * 3));
+}
+
+#[test]
+fn test_blit_frame_buffers() {
+  use ssd1325::FrameBuffer;
+
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Frame buffers are sent in the formats accepted by the blit functions.
+  let mut mono = ssd1325::FrameBufferL1::new();
+  mono.set_pixel(10, 10, 1);
+  display.blit_l1(mono.as_frame()).unwrap();
+  assert_eq!(log.borrow().len(), 3 + (64 * 3));
+  log.borrow_mut().clear();
+
+  let mut gray = mono.to_l4();
+  gray.set_pixel(11, 10, 0x8);
+  display.blit_l4(gray.as_frame()).unwrap();
+  assert_eq!(log.borrow().len(), 3 + (64 * 3));
+}
+
+#[test]
+fn test_blit_region() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Blit a 3x2 region starting on an odd column.
+  display.blit_region(5, 10, 3, 2, &[0xF; 6]).unwrap();
+
+  // Expected blit flow:
+  //  - Enter Command.
+  //  - Send Data (6).
+  //  - Enter Idle.
+  // [ 2x
+  //    - Enter Data.
+  //    - Send Data (2).
+  //    - Enter Idle.
+  // ]
+  let event_log = log.borrow_mut();
+  assert_eq!(event_log.len(), 3 + (2 * 3));
+
+  let mut event_log_iter = event_log.iter();
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterCommand);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::SendData);
+  assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+  for _ in 0 .. 2 {
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterData);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::SendData);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+  }
+}
+
+#[test]
+fn test_blit_region_invalid() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Regions which are empty or extend beyond the display are rejected.
+  let error = display.blit_region(120, 0, 9, 1, &[0; 9]).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+  let error = display.blit_region(0, 60, 1, 5, &[0; 5]).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+  let error = display.blit_region(0, 0, 0, 1, &[]).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+
+  // Pixel buffers must match the region exactly.
+  let error = display.blit_region(0, 0, 2, 2, &[0; 3]).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::BufferSizeMismatch));
+
+  // Nothing should have been sent to the display.
+  assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_fill_rect() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // A rectangle aligned to column pairs is filled entirely by the display.
+  display.fill_rect(0, 0, 127, 63, 0xF).unwrap();
+  assert_eq!(log.borrow().len(), 3);
+  log.borrow_mut().clear();
+
+  // A rectangle with odd edges sends each partially covered column separately.
+  display.fill_rect(1, 0, 4, 1, 0x8).unwrap();
+
+  // Expected fill flow:
+  //  - Enter Command, Send Data (Draw Rectangle), Enter Idle.
+  // [ 2x
+  //    - Enter Command, Send Data (Address Window), Enter Idle.
+  //    - 2x Enter Data, Send Data (1), Enter Idle.
+  // ]
+  let event_log = log.borrow();
+  assert_eq!(event_log.len(), 3 + 2 * (3 + 2 * 3));
+  assert_eq!(event_log[0], Event::ControlChannelEnterCommand);
+  assert_eq!(event_log[3], Event::ControlChannelEnterCommand);
+  assert_eq!(event_log[6], Event::ControlChannelEnterData);
+  assert_eq!(event_log[12], Event::ControlChannelEnterCommand);
+  assert_eq!(event_log[15], Event::ControlChannelEnterData);
+}
+
+#[test]
+fn test_draw_rect_outline() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  display.draw_rect_outline(10, 10, 20, 12, 0xF).unwrap();
+
+  // Expected outline flow:
+  //  - Enter Command, Send Data (Disable Fill), Enter Idle.
+  //  - Enter Command, Send Data (Draw Rectangle), Enter Idle.
+  // [ 2x
+  //    - Enter Command, Send Data (Address Window), Enter Idle.
+  //    - 3x Enter Data, Send Data (1), Enter Idle.
+  // ]
+  assert_eq!(log.borrow().len(), 3 + 3 + 2 * (3 + 3 * 3));
+  log.borrow_mut().clear();
+
+  // Filling re-enables the fill option before drawing.
+  display.fill_rect(0, 0, 1, 1, 0xF).unwrap();
+  assert_eq!(log.borrow().len(), 3 + 3);
+}
+
+#[test]
+fn test_fill_rect_invalid() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  let error = display.fill_rect(10, 0, 5, 0, 0x0).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+  let error = display.draw_rect_outline(0, 0, 0, 64, 0x0).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+  let error = display.fill_rect(0, 0, 1, 1, 0x10).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::ArgumentOutOfRange));
+
+  // Nothing should have been sent to the display.
+  assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_copy_region() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  let source = ssd1325::Rect { x0: 0, y0: 8, x1: 127, y1: 63 };
+  let destination = ssd1325::Point { x: 0, y: 0 };
+
+  // A plain copy only sends the Copy command.
+  display.copy_region(source, destination, ssd1325::CopyOptions::default()).unwrap();
+  assert_eq!(log.borrow().len(), 3);
+  log.borrow_mut().clear();
+
+  // Reversed and wrapping copies first configure graphic acceleration.
+  let options = ssd1325::CopyOptions { reverse: true, wrap_x: true };
+  display.copy_region(source, ssd1325::Point { x: 64, y: 0 }, options).unwrap();
+
+  // Expected copy flow:
+  //  - Enter Command, Send Data (Graphic Acceleration Options), Enter Idle.
+  //  - Enter Command, Send Data (Copy), Enter Idle.
+  let event_log = log.borrow();
+  assert_eq!(event_log.len(), 6);
+  assert_eq!(event_log[0], Event::ControlChannelEnterCommand);
+  assert_eq!(event_log[3], Event::ControlChannelEnterCommand);
+}
+
+#[test]
+fn test_copy_region_invalid() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+  let plain = ssd1325::CopyOptions::default();
+
+  // Columns must be aligned to pixel pairs.
+  let source = ssd1325::Rect { x0: 1, y0: 0, x1: 9, y1: 0 };
+  let error = display.copy_region(source, ssd1325::Point { x: 0, y: 0 }, plain).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::UnalignedRegion));
+  let source = ssd1325::Rect { x0: 0, y0: 0, x1: 9, y1: 0 };
+  let error = display.copy_region(source, ssd1325::Point { x: 3, y: 0 }, plain).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::UnalignedRegion));
+
+  // The destination must fit on the display unless wrapping.
+  let error = display.copy_region(source, ssd1325::Point { x: 120, y: 0 }, plain).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+  let error = display.copy_region(source, ssd1325::Point { x: 0, y: 64 }, plain).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+
+  // Nothing should have been sent to the display.
+  assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_horizontal_scroll() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Configure, start and stop scrolling of the bottom 16 rows.
+  display.set_horizontal_scroll(48, 63, 1, ssd1325::ScrollInterval::Frames6).unwrap();
+  display.set_scroll_active(true).unwrap();
+  display.set_scroll_active(false).unwrap();
+
+  // Expected scroll flow:
+  // [ 3x
+  //    - Enter Command.
+  //    - Send Data (Setup, Activate, Deactivate).
+  //    - Enter Idle.
+  // ]
+  let event_log = log.borrow();
+  assert_eq!(event_log.len(), 3 * 3);
+
+  let mut event_log_iter = event_log.iter();
+  for _ in 0 .. 3 {
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterCommand);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::SendData);
+    assert_eq!(event_log_iter.next().unwrap(), &Event::ControlChannelEnterIdle);
+  }
+}
+
+#[test]
+fn test_horizontal_scroll_invalid() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  let error = display.set_horizontal_scroll(10, 9, 1, ssd1325::ScrollInterval::Frames6).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+  let error = display.set_horizontal_scroll(0, 80, 1, ssd1325::ScrollInterval::Frames6).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::RegionOutOfBounds));
+  let error = display.set_horizontal_scroll(0, 63, 64, ssd1325::ScrollInterval::Frames200).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::ArgumentOutOfRange));
+
+  // Nothing should have been sent to the display.
+  assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_vertical_scroll() {
+  let (ref mut control, ref mut data, ref log) = create_test_setup();
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // Setting the scroll position sends a single command.
+  display.set_vertical_scroll(8).unwrap();
+  assert_eq!(log.borrow().len(), 3);
+  log.borrow_mut().clear();
+
+  // Scrolling smoothly sends one command per row, wrapping around display RAM.
+  display.smooth_scroll_to(4, time::Duration::from_millis(0)).unwrap();
+  assert_eq!(log.borrow().len(), 4 * 3);
+  log.borrow_mut().clear();
+  display.smooth_scroll_to(76, time::Duration::from_millis(0)).unwrap();
+  assert_eq!(log.borrow().len(), 8 * 3);
+  log.borrow_mut().clear();
+
+  // Lines beyond display RAM are rejected.
+  let error = display.set_vertical_scroll(80).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::ArgumentOutOfRange));
+  let error = display.smooth_scroll_to(80, time::Duration::from_millis(0)).unwrap_err();
+  assert_eq!(error.downcast_ref(), Some(&ssd1325::DisplayError::ArgumentOutOfRange));
+  assert!(log.borrow().is_empty());
+}
+
 #[test]
 fn test_simulate_write_zero_length() {
   let (ref mut control, ref mut data, _) = create_test_setup();
@@ -267,3 +707,128 @@ fn test_simulate_control_error() {
   // The control channel will indicate a failure occurred, which should yield an error.
   assert_eq!(display.set_on(true).is_err(), true);
 }
+
+#[test]
+#[cfg(feature = "emulator")]
+fn test_emulator_blit_l4() {
+  let emulator = ssd1325::Emulator::new();
+  let (ref mut control, ref mut data) = (emulator.clone(), emulator.clone());
+  let mut display = ssd1325::Ssd1325::new(data, control);
+  display.init().unwrap();
+  display.set_on(true).unwrap();
+  assert!(emulator.is_on());
+
+  // A blitted frame appears unchanged on the emulated panel.
+  let mut frame = [[0u8; 64]; 64];
+  for (y, line) in frame.iter_mut().enumerate() {
+    for (x, pixels) in line.iter_mut().enumerate() {
+      *pixels = ((x + y) as u8).wrapping_mul(0x13);
+    }
+  }
+  display.blit_l4(&frame).unwrap();
+  assert_eq!(emulator.visible_frame(), frame);
+
+  // Filling a rectangle with graphic acceleration and edge columns matches the frame buffer.
+  display.fill_rect(3, 5, 20, 9, 0xA).unwrap();
+  for line in frame[5 ..= 9].iter_mut() {
+    line[1] = (line[1] & 0xF0) | 0x0A;
+    for pixels in line[2 ..= 9].iter_mut() {
+      *pixels = 0xAA;
+    }
+    line[10] = (line[10] & 0x0F) | 0xA0;
+  }
+  assert_eq!(emulator.visible_frame(), frame);
+
+  display.clear().unwrap();
+  assert_eq!(emulator.visible_frame(), [[0u8; 64]; 64]);
+  assert!(emulator.unknown_commands().is_empty());
+}
+
+#[test]
+#[cfg(feature = "emulator")]
+fn test_emulator_orientation() {
+  let emulator = ssd1325::Emulator::new();
+  let (ref mut control, ref mut data) = (emulator.clone(), emulator.clone());
+  let mut display = ssd1325::Ssd1325::new(data, control);
+  display.init().unwrap();
+  display.set_on(true).unwrap();
+
+  let mut frame = [[0u8; 64]; 64];
+  frame[0][0] = 0x12;
+  frame[1][63] = 0x34;
+
+  // Flipping vertically shows the bottom row of the frame at the top of the panel.
+  display.set_orientation(ssd1325::Orientation::FlipV).unwrap();
+  display.blit_l4(&frame).unwrap();
+  let image = emulator.visible_image();
+  assert_eq!(image[63][0 .. 2], [0x1, 0x2]);
+  assert_eq!(image[62][126 .. 128], [0x3, 0x4]);
+
+  // Rotating by 180 degrees mirrors both axes.
+  display.set_orientation(ssd1325::Orientation::Rotate180).unwrap();
+  display.blit_l4(&frame).unwrap();
+  let image = emulator.visible_image();
+  assert_eq!(image[63][126 .. 128], [0x2, 0x1]);
+  assert_eq!(image[62][0 .. 2], [0x4, 0x3]);
+
+  // Inverting the display is reported as the view mode.
+  display.set_inverted(true).unwrap();
+  assert_eq!(emulator.view_mode(), ssd1325::ViewMode::Inverse);
+}
+
+#[test]
+#[cfg(feature = "emulator")]
+fn test_emulator_golden_image() {
+  let emulator = ssd1325::Emulator::new();
+  let (ref mut control, ref mut data) = (emulator.clone(), emulator.clone());
+  let mut display = ssd1325::Ssd1325::new(data, control);
+  display.init().unwrap();
+  display.set_on(true).unwrap();
+  display.fill_rect(8, 8, 23, 15, 0xF).unwrap();
+
+  let directory = std::env::temp_dir().join(format!("ssd1325-golden-{}", std::process::id()));
+  std::fs::create_dir_all(&directory).unwrap();
+  let golden = directory.join("rect.pgm");
+  let diff = directory.join("rect.diff.pgm");
+
+  // A snapshot of the panel matches itself.
+  emulator.save_pgm(&golden).unwrap();
+  emulator.assert_matches_golden(&golden);
+  assert!(!diff.exists());
+
+  // A change to the panel fails the comparison and writes a visual diff.
+  display.fill_rect(30, 30, 31, 31, 0x8).unwrap();
+  let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| emulator.assert_matches_golden(&golden)));
+  assert!(result.is_err());
+  let diff_image = ssd1325::Image::open(&diff).unwrap();
+  assert_eq!((diff_image.width(), diff_image.height()), (384, 64));
+  assert_eq!(diff_image.pixel(256 + 30, 30), Some(0xFF));
+  assert_eq!(diff_image.pixel(256 + 8, 8), Some(0x3F));
+
+  std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+#[cfg(feature = "emulator")]
+fn test_emulator_strict_mode() {
+  let emulator = ssd1325::Emulator::new();
+  emulator.set_strict(true);
+  let (ref mut control, ref mut data) = (emulator.clone(), emulator.clone());
+  let mut display = ssd1325::Ssd1325::new(data, control);
+
+  // The default configuration sends the undocumented VSL level of the Adafruit reference driver.
+  display.init().unwrap();
+  assert_eq!(emulator.violations(), [ssd1325::Violation::ArgumentOutOfRange { command: 0xBF, argument: 0x0F }]);
+  emulator.clear_violations();
+
+  // Frames may be sent once the display is initialized, before it is turned on.
+  display.init_with(ssd1325::PanelProfile::Adafruit27_128x64.config()).unwrap();
+  display.clear().unwrap();
+  display.blit_l4(&[[0x12; 64]; 64]).unwrap();
+  display.set_on(true).unwrap();
+  display.blit_l4(&[[0x12; 64]; 64]).unwrap();
+  display.clear().unwrap();
+  display.fill_rect(3, 5, 20, 9, 0xA).unwrap();
+  display.set_contrast(0x7F).unwrap();
+  assert_eq!(emulator.violations(), []);
+}